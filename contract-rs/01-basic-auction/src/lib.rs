@@ -1,6 +1,24 @@
 // Find all our documentation at https://docs.near.org
 use near_sdk::json_types::U64;
-use near_sdk::{env, near, require, AccountId, NearToken, PanicOnDefault, Promise};
+use near_sdk::store::{LookupMap, Vector};
+use near_sdk::{
+    env, near, require, AccountId, BorshStorageKey, Gas, NearToken, PanicOnDefault, Promise,
+    PromiseError,
+};
+
+pub mod events;
+pub mod history;
+pub mod refunds;
+pub mod reserve;
+pub mod sealed;
+pub mod status;
+pub use crate::events::*;
+pub use crate::history::*;
+pub use crate::reserve::*;
+pub use crate::sealed::*;
+pub use crate::status::*;
+
+const CLAIM_CALLBACK_GAS: Gas = Gas::from_tgas(5);
 
 #[near(serializers = [json, borsh])]
 #[derive(Clone)]
@@ -9,39 +27,118 @@ pub struct Bid {
     pub bid: NearToken,
 }
 
+#[near(serializers = [borsh])]
+#[derive(BorshStorageKey)]
+enum StorageKey {
+    PendingRefunds,
+    Commitments,
+    Bids,
+}
+
 #[near(contract_state)]
 #[derive(PanicOnDefault)]
 pub struct Contract {
     highest_bid: Bid,
+    runner_up_bid: Option<Bid>,
+    start_time: U64,
     auction_end_time: U64,
+    original_end_time: U64,
+    extension_window: Option<U64>,
+    extension_duration: Option<U64>,
+    max_extension: Option<U64>,
+    min_bid_increment: Option<BidIncrement>,
+    reserve_price: Option<ReservePrice>,
+    revealed_reserve: Option<NearToken>,
     auctioneer: AccountId,
-    claimed: bool,
+    status: AuctionStatus,
+    pending_refunds: LookupMap<AccountId, NearToken>,
+    mode: AuctionMode,
+    commitments: LookupMap<AccountId, Commitment>,
+    bids: Vector<BidRecord>,
 }
 
 #[near]
 impl Contract {
     #[init]
     #[private] // only callable by the contract's account
-    pub fn init(end_time: U64, auctioneer: AccountId) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        end_time: U64,
+        auctioneer: AccountId,
+        extension_window: Option<U64>,
+        extension_duration: Option<U64>,
+        max_extension: Option<U64>,
+        min_bid_increment: Option<BidIncrement>,
+        reserve_price: Option<ReservePrice>,
+        mode: Option<AuctionMode>,
+        start_time: Option<U64>,
+    ) -> Self {
+        // Without a start time the auction takes bids right away
+        let start_time = start_time.unwrap_or(U64(env::block_timestamp()));
+        require!(
+            start_time < end_time,
+            "The auction must start before it ends"
+        );
+        require!(
+            extension_window.is_some() == extension_duration.is_some(),
+            "extension_window and extension_duration must be set together"
+        );
+
+        let mode = mode.unwrap_or(AuctionMode::Open);
+        if let AuctionMode::Sealed {
+            reveal_end_time, ..
+        } = mode
+        {
+            require!(
+                reveal_end_time > end_time,
+                "The reveal phase must end after the auction"
+            );
+            require!(
+                extension_window.is_none(),
+                "Sealed auctions cannot be extended"
+            );
+        }
+
+        AuctionEvent::AuctionCreated {
+            auctioneer: &auctioneer,
+            end_time,
+        }
+        .emit();
+
         Self {
             highest_bid: Bid {
                 bidder: env::current_account_id(),
                 bid: NearToken::from_yoctonear(1),
             },
+            runner_up_bid: None,
+            start_time,
             auction_end_time: end_time,
-            claimed: false,
+            original_end_time: end_time,
+            extension_window,
+            extension_duration,
+            max_extension,
+            min_bid_increment,
+            reserve_price,
+            revealed_reserve: None,
+            status: AuctionStatus::Active,
             auctioneer,
+            pending_refunds: LookupMap::new(StorageKey::PendingRefunds),
+            mode,
+            commitments: LookupMap::new(StorageKey::Commitments),
+            bids: Vector::new(StorageKey::Bids),
         }
     }
 
     #[payable]
     pub fn bid(&mut self) -> Promise {
-        // Assert the auction is still ongoing
         require!(
-            env::block_timestamp() < self.auction_end_time.into(),
-            "Auction has ended"
+            matches!(self.mode, AuctionMode::Open),
+            "Bids must be committed with commit_bid"
         );
 
+        // Assert the auction is still ongoing
+        self.assert_status(&[AuctionStatus::Active]);
+
         // Current bid
         let bid = env::attached_deposit();
         let bidder = env::predecessor_account_id();
@@ -52,55 +149,168 @@ impl Contract {
             bid: last_bid,
         } = self.highest_bid.clone();
 
-        // Check if the deposit is higher than the current bid
-        require!(bid > last_bid, "You must place a higher bid");
+        // Check if the deposit is high enough to outbid the current bid
+        require!(bid >= self.get_minimum_bid(), "You must place a higher bid");
 
         // Update the highest bid
+        AuctionEvent::BidPlaced {
+            bidder: &bidder,
+            amount: bid,
+        }
+        .emit();
+        self.record_bid(&bidder, bid);
         self.highest_bid = Bid { bidder, bid };
 
+        // Give other bidders time to respond to a last-minute bid
+        self.extend_auction_end_time();
+
         // Transfer tokens back to the last bidder
-        Promise::new(last_bidder).transfer(last_bid)
+        self.refund(last_bidder, last_bid)
     }
 
     pub fn claim(&mut self) -> Promise {
+        // A payment to the auctioneer that failed is sent again
+        if self.status == AuctionStatus::Failed {
+            return self.pay_auctioneer();
+        }
+
+        self.assert_status(&[AuctionStatus::Ended]);
         require!(
-            env::block_timestamp() > self.auction_end_time.into(),
+            env::block_timestamp() > self.settlement_time(),
             "Auction has not ended yet"
         );
+        self.assert_reserve_can_be_settled();
 
-        require!(!self.claimed, "Auction has already been claimed");
-        self.claimed = true;
+        // If the reserve was not met the highest bidder gets their bid back,
+        // failed refunds are kept in the pending refunds ledger
+        if !self.reserve_met() {
+            self.status = AuctionStatus::Settled;
+            AuctionEvent::ReserveNotMet {
+                bidder: &self.highest_bid.bidder,
+                amount: self.highest_bid.bid,
+            }
+            .emit();
+            return self.refund(self.highest_bid.bidder.clone(), self.highest_bid.bid);
+        }
 
         // Transfer tokens to the auctioneer
-        Promise::new(self.auctioneer.clone()).transfer(self.highest_bid.bid)
+        let price = self.get_clearing_price();
+        AuctionEvent::AuctionClaimed {
+            winner: &self.highest_bid.bidder,
+            price,
+        }
+        .emit();
+        let transfer_to_auctioneer = self.pay_auctioneer();
+
+        // In second-price auctions the winner gets back what they bid over the price
+        if price < self.highest_bid.bid {
+            let change = self.highest_bid.bid.saturating_sub(price);
+            return transfer_to_auctioneer
+                .and(self.refund(self.highest_bid.bidder.clone(), change));
+        }
+
+        transfer_to_auctioneer
+    }
+
+    #[private]
+    pub fn resolve_claim(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        self.status = if transfer_result.is_ok() {
+            AuctionStatus::Settled
+        } else {
+            AuctionStatus::Failed
+        };
+        transfer_result.is_ok()
     }
 
     pub fn get_highest_bid(&self) -> Bid {
         self.highest_bid.clone()
     }
 
+    pub fn get_start_time(&self) -> U64 {
+        self.start_time
+    }
+
     pub fn get_auction_end_time(&self) -> U64 {
         self.auction_end_time
     }
 
+    pub fn get_original_end_time(&self) -> U64 {
+        self.original_end_time
+    }
+
     pub fn get_auctioneer(&self) -> AccountId {
         self.auctioneer.clone()
     }
+}
+
+impl Contract {
+    fn pay_auctioneer(&mut self) -> Promise {
+        self.status = AuctionStatus::Settling;
+
+        Promise::new(self.auctioneer.clone())
+            .transfer(self.get_clearing_price())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(CLAIM_CALLBACK_GAS)
+                    .resolve_claim(),
+            )
+    }
+
+    // Pushes the end of the auction forward when a bid arrives within the
+    // extension window, up to `max_extension` past the original end time
+    fn extend_auction_end_time(&mut self) {
+        let (Some(window), Some(duration)) = (self.extension_window, self.extension_duration)
+        else {
+            return;
+        };
+
+        let now = env::block_timestamp();
+        let end_time: u64 = self.auction_end_time.into();
+        if end_time.saturating_sub(now) >= window.into() {
+            return;
+        }
+
+        let mut new_end_time = now.saturating_add(duration.into());
+        if let Some(max_extension) = self.max_extension {
+            let latest_end_time =
+                u64::from(self.original_end_time).saturating_add(max_extension.into());
+            new_end_time = new_end_time.min(latest_end_time);
+        }
 
-    pub fn get_claimed(&self) -> bool {
-        self.claimed
+        if new_end_time > end_time {
+            self.auction_end_time = U64(new_end_time);
+            AuctionEvent::AuctionExtended {
+                end_time: self.auction_end_time,
+            }
+            .emit();
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseError};
 
     #[test]
     fn init_contract() {
         let end_time: U64 = U64::from(1000);
         let alice: AccountId = "alice.near".parse().unwrap();
-        let contract = Contract::init(end_time.clone(), alice.clone());
+        let contract = Contract::init(
+            end_time,
+            alice.clone(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
 
         let default_bid = contract.get_highest_bid();
         assert_eq!(default_bid.bidder, env::current_account_id());
@@ -113,6 +323,313 @@ mod tests {
         assert_eq!(auctioneer, alice);
 
         let claimed = contract.get_claimed();
-        assert_eq!(claimed, false);
+        assert!(!claimed);
+        assert_eq!(contract.get_status(), AuctionStatus::Active);
+    }
+
+    #[test]
+    fn failed_refund_can_be_withdrawn() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let refund = NearToken::from_near(1);
+
+        // Successful transfers are not recorded
+        contract.resolve_refund(alice.clone(), refund, Ok(()));
+        assert!(contract.get_pending_refund(alice.clone()).is_zero());
+
+        // Failed transfers are kept in the ledger
+        contract.resolve_refund(alice.clone(), refund, Err(PromiseError::Failed));
+        contract.resolve_refund(alice.clone(), refund, Err(PromiseError::Failed));
+        assert_eq!(
+            contract.get_pending_refund(alice.clone()),
+            NearToken::from_near(2)
+        );
+
+        // Withdrawing clears the ledger entry
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(alice.clone())
+            .build());
+        let _ = contract.withdraw_refund();
+        assert!(contract.get_pending_refund(alice).is_zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction has not started yet")]
+    fn bids_wait_for_the_start_time() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(U64::from(500)),
+        );
+        assert_eq!(contract.get_status(), AuctionStatus::Pending);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .attached_deposit(NearToken::from_near(1))
+            .block_timestamp(400)
+            .build());
+        let _ = contract.bid();
+    }
+
+    #[test]
+    fn failed_payment_can_be_claimed_again() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .attached_deposit(NearToken::from_near(1))
+            .block_timestamp(500)
+            .build());
+        let _ = contract.bid();
+        assert_eq!(contract.get_status(), AuctionStatus::Active);
+
+        testing_env!(VMContextBuilder::new().block_timestamp(1500).build());
+        assert_eq!(contract.get_status(), AuctionStatus::Ended);
+        let _ = contract.claim();
+        assert_eq!(contract.get_status(), AuctionStatus::Settling);
+
+        // The auctioneer could not be paid, the payment can be sent again
+        contract.resolve_claim(Err(PromiseError::Failed));
+        assert_eq!(contract.get_status(), AuctionStatus::Failed);
+        assert!(!contract.get_claimed());
+        let _ = contract.claim();
+        assert_eq!(contract.get_status(), AuctionStatus::Settling);
+
+        contract.resolve_claim(Ok(()));
+        assert_eq!(contract.get_status(), AuctionStatus::Settled);
+    }
+
+    #[test]
+    fn late_bid_extends_auction() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            Some(U64::from(100)),
+            Some(U64::from(200)),
+            Some(U64::from(300)),
+            None,
+            None,
+            None,
+            None,
+        );
+
+        // A bid outside of the window does not extend the auction
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(alice.clone())
+            .attached_deposit(NearToken::from_near(1))
+            .block_timestamp(500)
+            .build());
+        let _ = contract.bid();
+        assert_eq!(contract.get_auction_end_time(), U64::from(1000));
+
+        // A bid within the window pushes the end time forward
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(bob.clone())
+            .attached_deposit(NearToken::from_near(2))
+            .block_timestamp(950)
+            .build());
+        let _ = contract.bid();
+        assert_eq!(contract.get_auction_end_time(), U64::from(1150));
+
+        // Extensions are capped at `max_extension` past the original end time
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(alice)
+            .attached_deposit(NearToken::from_near(3))
+            .block_timestamp(1100)
+            .build());
+        let _ = contract.bid();
+        assert_eq!(contract.get_auction_end_time(), U64::from(1300));
+        assert_eq!(contract.get_original_end_time(), U64::from(1000));
+
+        // Every bid is kept in the history
+        assert_eq!(contract.get_bid_count(), 3);
+        let bids = contract.get_bids(Some(1), Some(1));
+        assert_eq!(bids.len(), 1);
+        assert_eq!(bids[0].bidder, bob);
+        assert_eq!(bids[0].amount, NearToken::from_near(2));
+        assert_eq!(bids[0].timestamp, U64::from(950));
+    }
+
+    #[test]
+    fn bids_respect_increment_and_hidden_reserve() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let auctioneer: AccountId = "auctioneer.near".parse().unwrap();
+        let reserve_hash =
+            env::sha256(format!("{}:salt", NearToken::from_near(5).as_yoctonear()).as_bytes());
+        let mut contract = Contract::init(
+            U64::from(1000),
+            auctioneer.clone(),
+            None,
+            None,
+            None,
+            Some(BidIncrement::BasisPoints(1_000)),
+            Some(ReservePrice::Hidden(reserve_hash.into())),
+            None,
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(alice)
+            .attached_deposit(NearToken::from_near(10))
+            .build());
+        let _ = contract.bid();
+
+        // The next bid must be at least 10% higher
+        assert_eq!(contract.get_minimum_bid(), NearToken::from_near(11));
+
+        // The reserve stays hidden until the auctioneer reveals it
+        assert!(contract.get_reserve_price().is_none());
+        assert!(!contract.reserve_met());
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(auctioneer)
+            .block_timestamp(1001)
+            .build());
+        contract.reveal_reserve(NearToken::from_near(5), "salt".to_string());
+        assert_eq!(contract.get_reserve_price(), Some(NearToken::from_near(5)));
+        assert!(contract.reserve_met());
+    }
+
+    #[test]
+    #[should_panic(expected = "The reserve has not been revealed yet")]
+    fn claims_wait_for_the_hidden_reserve() {
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let reserve_hash =
+            env::sha256(format!("{}:salt", NearToken::from_near(5).as_yoctonear()).as_bytes());
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            None,
+            None,
+            None,
+            None,
+            Some(ReservePrice::Hidden(reserve_hash.into())),
+            None,
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(bob.clone())
+            .attached_deposit(NearToken::from_near(10))
+            .build());
+        let _ = contract.bid();
+
+        // Only the auctioneer can claim until the reveal period is over
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(bob)
+            .block_timestamp(1000 + RESERVE_REVEAL_PERIOD)
+            .build());
+        let _ = contract.claim();
+    }
+
+    #[test]
+    fn sealed_bids_are_revealed_after_the_auction() {
+        let carol: AccountId = "carol.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(AuctionMode::Sealed {
+                reveal_end_time: U64::from(2000),
+                second_price: true,
+            }),
+            None,
+        );
+
+        // Each bidder commits to a bid, hiding it behind a larger deposit
+        for (bidder, bid, deposit) in [(&carol, 3, 5), (&bob, 4, 4)] {
+            let preimage = format!(
+                "{}:{}:salt",
+                bidder,
+                NearToken::from_near(bid).as_yoctonear()
+            );
+            testing_env!(VMContextBuilder::new()
+                .predecessor_account_id(bidder.clone())
+                .attached_deposit(NearToken::from_near(deposit))
+                .block_timestamp(500)
+                .build());
+            contract.commit_bid(env::sha256(preimage.as_bytes()).into());
+        }
+        assert_eq!(contract.get_highest_bid().bidder, env::current_account_id());
+
+        // Once the auction ends the bids are revealed
+        for (bidder, bid) in [(&carol, 3), (&bob, 4)] {
+            testing_env!(VMContextBuilder::new()
+                .predecessor_account_id(bidder.clone())
+                .block_timestamp(1500)
+                .build());
+            contract.reveal_bid(NearToken::from_near(bid), "salt".to_string());
+            assert!(contract.get_commitment(bidder.clone()).is_none());
+        }
+
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, bob);
+        assert_eq!(highest_bid.bid, NearToken::from_near(4));
+
+        // Bob wins, but only pays Carol's bid
+        assert_eq!(contract.get_runner_up_bid().unwrap().bidder, carol);
+        assert_eq!(contract.get_clearing_price(), NearToken::from_near(3));
+    }
+
+    #[test]
+    fn bids_emit_events() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .attached_deposit(NearToken::from_near(1))
+            .build());
+        let _ = contract.bid();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"auction","version":"1.0.0","event":"bid_placed","data":{"bidder":"bob.near","amount":"1000000000000000000000000"}}"#,
+                r#"EVENT_JSON:{"standard":"auction","version":"1.0.0","event":"bid_refunded","data":{"bidder":"alice.near","amount":"1"}}"#,
+            ]
+        );
     }
 }
@@ -0,0 +1,69 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::{env, near, require, AccountId, Gas, NearToken, Promise, PromiseError};
+
+use crate::{AuctionEvent, Contract, ContractExt};
+
+const REFUND_CALLBACK_GAS: Gas = Gas::from_tgas(5);
+
+#[near]
+impl Contract {
+    // Outbid users can pull any refund that could not be pushed to them
+    pub fn withdraw_refund(&mut self) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let amount = self
+            .pending_refunds
+            .remove(&account_id)
+            .unwrap_or(NearToken::from_yoctonear(0));
+
+        require!(!amount.is_zero(), "No pending refund");
+
+        self.refund(account_id, amount)
+    }
+
+    pub fn get_pending_refund(&self, account_id: AccountId) -> NearToken {
+        self.pending_refunds
+            .get(&account_id)
+            .copied()
+            .unwrap_or(NearToken::from_yoctonear(0))
+    }
+
+    #[private]
+    pub fn resolve_refund(
+        &mut self,
+        account_id: AccountId,
+        amount: NearToken,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        if transfer_result.is_ok() {
+            return true;
+        }
+
+        // The transfer failed and the tokens came back to the contract,
+        // keep track of them so the user can withdraw them later
+        AuctionEvent::RefundFailed {
+            bidder: &account_id,
+            amount,
+        }
+        .emit();
+        let pending = self.get_pending_refund(account_id.clone());
+        self.pending_refunds
+            .insert(account_id, pending.saturating_add(amount));
+        false
+    }
+}
+
+impl Contract {
+    // Pushes a refund, falling back to the pending refunds ledger if it fails
+    pub(crate) fn refund(&self, account_id: AccountId, amount: NearToken) -> Promise {
+        AuctionEvent::BidRefunded {
+            bidder: &account_id,
+            amount,
+        }
+        .emit();
+        Promise::new(account_id.clone()).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(REFUND_CALLBACK_GAS)
+                .resolve_refund(account_id, amount),
+        )
+    }
+}
@@ -0,0 +1,105 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, near, require, NearToken};
+
+use crate::{AuctionEvent, AuctionStatus, Contract, ContractExt};
+
+// Time the auctioneer has after the auction to reveal a hidden reserve
+// before anyone else can claim it (one day, in nanoseconds)
+pub const RESERVE_REVEAL_PERIOD: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone)]
+pub enum BidIncrement {
+    // Fixed amount over the current highest bid
+    Absolute(NearToken),
+    // Percentage of the current highest bid, in basis points (1/100 of a percent)
+    BasisPoints(u16),
+}
+
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone)]
+pub enum ReservePrice {
+    // The reserve is known by everyone from the start
+    Public(NearToken),
+    // Only the sha256 hash of "{reserve in yoctoNEAR}:{salt}" is stored,
+    // the auctioneer reveals the reserve once the auction has ended
+    Hidden(Base64VecU8),
+}
+
+#[near]
+impl Contract {
+    pub fn reveal_reserve(&mut self, amount: NearToken, salt: String) {
+        require!(
+            env::predecessor_account_id() == self.auctioneer,
+            "Only the auctioneer can reveal the reserve"
+        );
+        self.assert_status(&[AuctionStatus::Ended]);
+
+        let Some(ReservePrice::Hidden(hash)) = &self.reserve_price else {
+            env::panic_str("There is no hidden reserve to reveal");
+        };
+
+        let preimage = format!("{}:{}", amount.as_yoctonear(), salt);
+        require!(
+            env::sha256(preimage.as_bytes()) == hash.0,
+            "The amount and salt do not match the reserve"
+        );
+
+        self.revealed_reserve = Some(amount);
+        AuctionEvent::ReserveRevealed { amount }.emit();
+    }
+
+    // Returns the reserve price, or None if there is none or it is still hidden
+    pub fn get_reserve_price(&self) -> Option<NearToken> {
+        match &self.reserve_price {
+            Some(ReservePrice::Public(amount)) => Some(*amount),
+            Some(ReservePrice::Hidden(_)) => self.revealed_reserve,
+            None => None,
+        }
+    }
+
+    pub fn get_minimum_bid(&self) -> NearToken {
+        let last_bid = self.highest_bid.bid;
+        let increment = match &self.min_bid_increment {
+            Some(BidIncrement::Absolute(amount)) => *amount,
+            Some(BidIncrement::BasisPoints(basis_points)) => last_bid
+                .saturating_mul(*basis_points as u128)
+                .saturating_div(10_000),
+            None => NearToken::from_yoctonear(0),
+        };
+
+        // A new bid always needs to be strictly higher than the last one
+        last_bid.saturating_add(increment.max(NearToken::from_yoctonear(1)))
+    }
+}
+
+impl Contract {
+    // While a hidden reserve is not revealed only the auctioneer can claim,
+    // otherwise anyone could settle the auction as not meeting the reserve
+    // before the auctioneer had the chance to reveal it
+    pub(crate) fn assert_reserve_can_be_settled(&self) {
+        let reserve_hidden = matches!(self.reserve_price, Some(ReservePrice::Hidden(_)))
+            && self.revealed_reserve.is_none();
+        if !reserve_hidden || env::predecessor_account_id() == self.auctioneer {
+            return;
+        }
+
+        require!(
+            env::block_timestamp() > self.settlement_time().saturating_add(RESERVE_REVEAL_PERIOD),
+            "The reserve has not been revealed yet"
+        );
+    }
+
+    // A hidden reserve that was never revealed is treated as not met
+    pub(crate) fn reserve_met(&self) -> bool {
+        if self.reserve_price.is_none() {
+            return true;
+        }
+
+        self.get_reserve_price()
+            .is_some_and(|reserve| self.highest_bid.bid >= reserve)
+    }
+}
@@ -0,0 +1,214 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::{env, near, require, AccountId, NearToken};
+
+use crate::{AuctionEvent, AuctionStatus, Bid, Contract, ContractExt};
+
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone)]
+pub enum AuctionMode {
+    // Bids are placed in the open with `bid`
+    Open,
+    // Bids are committed until the auction ends and revealed until `reveal_end_time`,
+    // with `second_price` the winner pays the second highest bid (Vickrey auction)
+    Sealed {
+        reveal_end_time: U64,
+        #[serde(default)]
+        second_price: bool,
+    },
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct Commitment {
+    // sha256 hash of "{bidder}:{bid in yoctoNEAR}:{salt}"
+    pub hash: Base64VecU8,
+    // Tokens attached to the commitment, must cover the revealed bid
+    pub deposit: NearToken,
+}
+
+#[near]
+impl Contract {
+    #[payable]
+    pub fn commit_bid(&mut self, commitment: Base64VecU8) {
+        require!(
+            matches!(self.mode, AuctionMode::Sealed { .. }),
+            "The auction does not take sealed bids"
+        );
+        self.assert_status(&[AuctionStatus::Active]);
+        require!(
+            commitment.0.len() == 32,
+            "The commitment must be a sha256 hash"
+        );
+
+        let bidder = env::predecessor_account_id();
+        require!(
+            !self.commitments.contains_key(&bidder),
+            "You have already committed a bid"
+        );
+
+        let deposit = env::attached_deposit();
+        require!(!deposit.is_zero(), "You must attach a deposit");
+
+        AuctionEvent::BidCommitted {
+            bidder: &bidder,
+            deposit,
+        }
+        .emit();
+
+        self.commitments.insert(
+            bidder,
+            Commitment {
+                hash: commitment,
+                deposit,
+            },
+        );
+    }
+
+    pub fn reveal_bid(&mut self, bid: NearToken, salt: String) {
+        let AuctionMode::Sealed {
+            reveal_end_time, ..
+        } = self.mode
+        else {
+            env::panic_str("The auction does not take sealed bids");
+        };
+
+        // The reveal phase starts when the auction ends
+        self.assert_status(&[AuctionStatus::Ended]);
+        require!(
+            env::block_timestamp() <= reveal_end_time.into(),
+            "The reveal phase has ended"
+        );
+
+        let bidder = env::predecessor_account_id();
+        let Some(Commitment { hash, deposit }) = self.commitments.get(&bidder).cloned() else {
+            env::panic_str("You have not committed a bid");
+        };
+
+        let preimage = format!("{}:{}:{}", bidder, bid.as_yoctonear(), salt);
+        require!(
+            env::sha256(preimage.as_bytes()) == hash.0,
+            "The bid and salt do not match the commitment"
+        );
+        self.commitments.remove(&bidder);
+
+        // A bid that is not covered by the deposit is not valid
+        if bid > deposit {
+            let _ = self.refund(bidder, deposit);
+            return;
+        }
+
+        AuctionEvent::BidRevealed {
+            bidder: &bidder,
+            amount: bid,
+        }
+        .emit();
+        self.record_bid(&bidder, bid);
+
+        // A bid that does not beat the current highest bid is refunded,
+        // but it might set the price the winner pays
+        if bid <= self.highest_bid.bid {
+            if self
+                .runner_up_bid
+                .as_ref()
+                .is_none_or(|runner_up| bid > runner_up.bid)
+            {
+                self.runner_up_bid = Some(Bid {
+                    bidder: bidder.clone(),
+                    bid,
+                });
+            }
+            let _ = self.refund(bidder, deposit);
+            return;
+        }
+
+        let Bid {
+            bidder: last_bidder,
+            bid: last_bid,
+        } = self.highest_bid.clone();
+
+        self.highest_bid = Bid {
+            bidder: bidder.clone(),
+            bid,
+        };
+
+        if last_bidder != env::current_account_id() {
+            self.runner_up_bid = Some(Bid {
+                bidder: last_bidder.clone(),
+                bid: last_bid,
+            });
+        }
+
+        // Return the previous highest bid and whatever the deposit had on top of the bid
+        let _ = self.refund(last_bidder, last_bid);
+        if deposit > bid {
+            let _ = self.refund(bidder, deposit.saturating_sub(bid));
+        }
+    }
+
+    // Commitments that were never revealed can be withdrawn once the reveal phase is over
+    pub fn withdraw_commitment(&mut self) {
+        let AuctionMode::Sealed {
+            reveal_end_time, ..
+        } = self.mode
+        else {
+            env::panic_str("The auction does not take sealed bids");
+        };
+        require!(
+            env::block_timestamp() > reveal_end_time.into(),
+            "The reveal phase has not ended yet"
+        );
+
+        let bidder = env::predecessor_account_id();
+        let Some(Commitment { deposit, .. }) = self.commitments.remove(&bidder) else {
+            env::panic_str("You have not committed a bid");
+        };
+
+        let _ = self.refund(bidder, deposit);
+    }
+
+    pub fn get_commitment(&self, account_id: AccountId) -> Option<Commitment> {
+        self.commitments.get(&account_id).cloned()
+    }
+
+    pub fn get_auction_mode(&self) -> AuctionMode {
+        self.mode.clone()
+    }
+
+    pub fn get_runner_up_bid(&self) -> Option<Bid> {
+        self.runner_up_bid.clone()
+    }
+
+    // Amount the highest bidder pays when the auction is claimed
+    pub fn get_clearing_price(&self) -> NearToken {
+        let AuctionMode::Sealed {
+            second_price: true, ..
+        } = self.mode
+        else {
+            return self.highest_bid.bid;
+        };
+
+        // Without other bids the winner pays the reserve, if there is one
+        let runner_up = self
+            .runner_up_bid
+            .as_ref()
+            .map_or(NearToken::from_yoctonear(0), |runner_up| runner_up.bid);
+        let reserve = self
+            .get_reserve_price()
+            .unwrap_or(NearToken::from_yoctonear(0));
+        runner_up.max(reserve).min(self.highest_bid.bid)
+    }
+}
+
+impl Contract {
+    // Sealed auctions can only be claimed once all bids had the chance to be revealed
+    pub(crate) fn settlement_time(&self) -> u64 {
+        match self.mode {
+            AuctionMode::Open => self.auction_end_time.into(),
+            AuctionMode::Sealed {
+                reveal_end_time, ..
+            } => reveal_end_time.into(),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::{env, near};
+
+use crate::{Contract, ContractExt};
+
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AuctionStatus {
+    // The auction does not take bids before its start time
+    Pending,
+    Active,
+    // Bidding is over and the auction waits to be claimed, it is never
+    // stored: an active auction is ended once its end time is reached
+    Ended,
+    // The auction was claimed and the payment to the auctioneer was sent
+    Settling,
+    Settled,
+    Cancelled,
+    // The payment to the auctioneer failed and can be sent again with `claim`
+    Failed,
+}
+
+#[near]
+impl Contract {
+    pub fn get_status(&self) -> AuctionStatus {
+        let now = env::block_timestamp();
+        match self.status {
+            AuctionStatus::Active if now < self.start_time.into() => AuctionStatus::Pending,
+            AuctionStatus::Active if now >= self.auction_end_time.into() => AuctionStatus::Ended,
+            status => status,
+        }
+    }
+
+    // Kept for the frontends and the other implementations of the auction,
+    // an auction whose payment failed can be claimed again
+    pub fn get_claimed(&self) -> bool {
+        matches!(
+            self.status,
+            AuctionStatus::Settling | AuctionStatus::Settled
+        )
+    }
+}
+
+impl Contract {
+    // Every method that changes the auction checks first that it is allowed
+    // in the current status
+    pub(crate) fn assert_status(&self, allowed: &[AuctionStatus]) {
+        let status = self.get_status();
+        if allowed.contains(&status) {
+            return;
+        }
+
+        env::panic_str(match status {
+            AuctionStatus::Pending => "Auction has not started yet",
+            AuctionStatus::Active => "Auction has not ended yet",
+            AuctionStatus::Ended => "Auction has ended",
+            AuctionStatus::Settling | AuctionStatus::Settled | AuctionStatus::Failed => {
+                "Auction has already been claimed"
+            }
+            AuctionStatus::Cancelled => "The auction has been cancelled",
+        })
+    }
+}
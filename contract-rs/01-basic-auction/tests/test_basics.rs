@@ -108,6 +108,27 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .total;
     assert!(new_alice_balance == alice_balance.saturating_add(NearToken::from_near(1)));
 
+    // The refund was pushed successfully, so nothing is left in the ledger
+    let pending_refund: NearToken = contract
+        .call_function(
+            "get_pending_refund",
+            json!({"account_id": alice.account_id()}),
+        )
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(pending_refund, NearToken::from_yoctonear(0));
+
+    // Alice has nothing to withdraw
+    contract
+        .call_function("withdraw_refund", ())
+        .transaction()
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
     // Alice tries to make a bid with less NEAR than the previous
     contract
         .call_function("bid", ())
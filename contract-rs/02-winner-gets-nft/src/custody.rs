@@ -0,0 +1,129 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::U64;
+use near_sdk::serde_json;
+use near_sdk::{env, near, require, AccountId, Gas, Promise, PromiseError};
+
+use crate::{
+    nft_contract, AuctionMode, AuctionStatus, Contract, ContractExt, JsonToken, ReservePrice,
+    TokenId,
+};
+
+const NFT_TOKEN_GAS: Gas = Gas::from_tgas(10);
+const ACTIVATE_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+// The `msg` of `nft_transfer_call` when the auctioneer hands over the NFT,
+// any field that is set replaces the value passed to `init`
+#[near(serializers = [json])]
+#[derive(Default)]
+pub struct AuctionMsg {
+    pub end_time: Option<U64>,
+    pub reserve_price: Option<ReservePrice>,
+}
+
+#[near]
+impl Contract {
+    // Called by the NFT contract when the auctioneer transfers the NFT with
+    // `nft_transfer_call`, the auction takes bids from then on
+    #[allow(unused_variables)]
+    pub fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> bool {
+        require!(
+            env::predecessor_account_id() == self.nft_contract && token_id == self.token_id,
+            "This is not the NFT being auctioned"
+        );
+        require!(
+            previous_owner_id == self.auctioneer,
+            "Only the auctioneer can transfer the NFT"
+        );
+        require!(
+            self.status == AuctionStatus::Pending,
+            "The NFT has already been received"
+        );
+
+        let AuctionMsg {
+            end_time,
+            reserve_price,
+        } = if msg.is_empty() {
+            AuctionMsg::default()
+        } else {
+            serde_json::from_str(&msg)
+                .unwrap_or_else(|_| env::panic_str("The msg is not a valid auction config"))
+        };
+
+        if let Some(end_time) = end_time {
+            require!(
+                end_time.0 > env::block_timestamp(),
+                "The auction must end in the future"
+            );
+            require!(
+                end_time > self.start_time,
+                "The auction must start before it ends"
+            );
+            if let AuctionMode::Sealed {
+                reveal_end_time, ..
+            } = self.mode
+            {
+                require!(
+                    reveal_end_time > end_time,
+                    "The reveal phase must end after the auction"
+                );
+            }
+            self.auction_end_time = end_time;
+            self.original_end_time = end_time;
+        }
+        if reserve_price.is_some() {
+            self.reserve_price = reserve_price;
+            Self::assert_buy_now_price(self.buy_now_price, &self.reserve_price);
+        }
+
+        self.status = AuctionStatus::Active;
+
+        // Keep the NFT
+        false
+    }
+
+    // For NFTs that were sent to the contract with a plain `nft_transfer`,
+    // e.g. auctions created through the factory, anyone can ask the contract
+    // to check with the NFT contract that it owns the token
+    pub fn activate(&self) -> Promise {
+        require!(
+            self.status == AuctionStatus::Pending,
+            "The auction is already active"
+        );
+
+        nft_contract::ext(self.nft_contract.clone())
+            .with_static_gas(NFT_TOKEN_GAS)
+            .nft_token(self.token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(ACTIVATE_CALLBACK_GAS)
+                    .resolve_activate(),
+            )
+    }
+
+    #[private]
+    pub fn resolve_activate(
+        &mut self,
+        #[callback_result] token: Result<Option<JsonToken>, PromiseError>,
+    ) -> bool {
+        let owned = matches!(
+            token,
+            Ok(Some(JsonToken { token_id, owner_id }))
+                if token_id == self.token_id && owner_id == env::current_account_id()
+        );
+        // The auction could have been activated while the call was in flight
+        if owned && self.status == AuctionStatus::Pending {
+            self.status = AuctionStatus::Active;
+        }
+        owned
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.get_status() == AuctionStatus::Active
+    }
+}
@@ -0,0 +1,50 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::U64;
+use near_sdk::{near, AccountId, NearToken};
+
+// NEP-297 events, logged as `EVENT_JSON:{"standard":"auction",...}` so
+// indexers can follow the auction without polling its state
+#[near(event_json(standard = "auction"))]
+pub enum AuctionEvent<'a> {
+    #[event_version("1.0.0")]
+    AuctionCreated {
+        auctioneer: &'a AccountId,
+        end_time: U64,
+    },
+    #[event_version("1.0.0")]
+    BidPlaced {
+        bidder: &'a AccountId,
+        amount: NearToken,
+    },
+    #[event_version("1.0.0")]
+    BidRefunded {
+        bidder: &'a AccountId,
+        amount: NearToken,
+    },
+    #[event_version("1.0.0")]
+    AuctionExtended { end_time: U64 },
+    #[event_version("1.0.0")]
+    BidCommitted {
+        bidder: &'a AccountId,
+        deposit: NearToken,
+    },
+    #[event_version("1.0.0")]
+    BidRevealed {
+        bidder: &'a AccountId,
+        amount: NearToken,
+    },
+    #[event_version("1.0.0")]
+    ReserveRevealed { amount: NearToken },
+    #[event_version("1.0.0")]
+    ReserveNotMet {
+        bidder: &'a AccountId,
+        amount: NearToken,
+    },
+    #[event_version("1.0.0")]
+    AuctionClaimed {
+        winner: &'a AccountId,
+        price: NearToken,
+    },
+    #[event_version("1.0.0")]
+    AuctionCancelled { auctioneer: &'a AccountId },
+}
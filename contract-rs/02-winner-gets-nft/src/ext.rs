@@ -1,11 +1,19 @@
 // Find all our documentation at https://docs.near.org
-use near_sdk::{ext_contract, AccountId};
+use near_sdk::{ext_contract, near, AccountId};
 
 use crate::TokenId;
 
+// The part of a NEP-171 token returned by `nft_token` that the auction needs
+#[near(serializers = [json])]
+pub struct JsonToken {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+}
+
 // NFT interface for cross-contract calls
 #[ext_contract(nft_contract)]
 #[allow(dead_code)]
-trait NFT {
+trait Nft {
     fn nft_transfer(&self, receiver_id: AccountId, token_id: TokenId);
+    fn nft_token(&self, token_id: TokenId) -> Option<JsonToken>;
 }
@@ -1,9 +1,28 @@
 // Find all our documentation at https://docs.near.org
 use near_sdk::json_types::U64;
-use near_sdk::{env, near, require, AccountId, Gas, NearToken, PanicOnDefault, Promise};
+use near_sdk::store::{LookupMap, Vector};
+use near_sdk::{
+    env, near, require, AccountId, BorshStorageKey, NearToken, PanicOnDefault, Promise,
+};
 
+pub mod cancel;
+pub mod custody;
+pub mod events;
 pub mod ext;
+pub mod history;
+pub mod proxy;
+pub mod reserve;
+pub mod sealed;
+pub mod settlement;
+pub mod status;
+pub use crate::custody::*;
+pub use crate::events::*;
 pub use crate::ext::*;
+pub use crate::history::*;
+pub use crate::reserve::*;
+pub use crate::sealed::*;
+pub use crate::settlement::*;
+pub use crate::status::*;
 
 #[near(serializers = [json, borsh])]
 #[derive(Clone)]
@@ -14,47 +33,149 @@ pub struct Bid {
 
 pub type TokenId = String;
 
+#[near(serializers = [borsh])]
+#[derive(BorshStorageKey)]
+enum StorageKey {
+    Commitments,
+    Bids,
+}
+
 #[near(contract_state, serializers = [json, borsh])]
 #[derive(PanicOnDefault)]
 pub struct Contract {
     highest_bid: Bid,
+    // The maximum escrowed by the highest bidder when they placed a proxy bid
+    max_bid: Option<NearToken>,
+    runner_up_bid: Option<Bid>,
+    start_time: U64,
     auction_end_time: U64,
+    original_end_time: U64,
+    extension_window: Option<U64>,
+    extension_duration: Option<U64>,
+    max_extension: Option<U64>,
+    min_bid_increment: Option<BidIncrement>,
+    reserve_price: Option<ReservePrice>,
+    revealed_reserve: Option<NearToken>,
+    buy_now_price: Option<NearToken>,
     auctioneer: AccountId,
+    status: AuctionStatus,
+    // Set once the settlement starts, the frontends read it from the auction info
     claimed: bool,
+    settlement: Option<Settlement>,
     nft_contract: AccountId,
     token_id: TokenId,
+    mode: AuctionMode,
+    // Sealed bids committed and not revealed or withdrawn yet
+    open_commitments: u32,
+    // Collections are not part of the auction info
+    #[serde(skip, default = "commitments")]
+    commitments: LookupMap<AccountId, Commitment>,
+    #[serde(skip, default = "bids")]
+    bids: Vector<BidRecord>,
+}
+
+fn commitments() -> LookupMap<AccountId, Commitment> {
+    LookupMap::new(StorageKey::Commitments)
+}
+
+fn bids() -> Vector<BidRecord> {
+    Vector::new(StorageKey::Bids)
 }
 
 #[near]
 impl Contract {
     #[init]
     #[private] // only callable by the contract's account
+    #[allow(clippy::too_many_arguments)]
     pub fn init(
         end_time: U64,
         auctioneer: AccountId,
         nft_contract: AccountId,
         token_id: TokenId,
+        extension_window: Option<U64>,
+        extension_duration: Option<U64>,
+        max_extension: Option<U64>,
+        min_bid_increment: Option<BidIncrement>,
+        reserve_price: Option<ReservePrice>,
+        mode: Option<AuctionMode>,
+        buy_now_price: Option<NearToken>,
+        start_time: Option<U64>,
     ) -> Self {
+        // Without a start time the auction takes bids as soon as it holds the NFT
+        let start_time = start_time.unwrap_or(U64(env::block_timestamp()));
+        require!(
+            start_time < end_time,
+            "The auction must start before it ends"
+        );
+        require!(
+            extension_window.is_some() == extension_duration.is_some(),
+            "extension_window and extension_duration must be set together"
+        );
+
+        let mode = mode.unwrap_or(AuctionMode::Open);
+        if let AuctionMode::Sealed {
+            reveal_end_time, ..
+        } = mode
+        {
+            require!(
+                reveal_end_time > end_time,
+                "The reveal phase must end after the auction"
+            );
+            require!(
+                extension_window.is_none(),
+                "Sealed auctions cannot be extended"
+            );
+            require!(
+                buy_now_price.is_none(),
+                "Sealed auctions cannot have a buy-now price"
+            );
+        }
+
+        Self::assert_buy_now_price(buy_now_price, &reserve_price);
+
+        AuctionEvent::AuctionCreated {
+            auctioneer: &auctioneer,
+            end_time,
+        }
+        .emit();
+
         Self {
             highest_bid: Bid {
                 bidder: env::current_account_id(),
                 bid: NearToken::from_yoctonear(1),
             },
+            max_bid: None,
+            runner_up_bid: None,
+            start_time,
             auction_end_time: end_time,
+            original_end_time: end_time,
+            extension_window,
+            extension_duration,
+            max_extension,
+            min_bid_increment,
+            reserve_price,
+            revealed_reserve: None,
+            buy_now_price,
             auctioneer,
+            status: AuctionStatus::Pending,
             claimed: false,
+            settlement: None,
             nft_contract,
             token_id,
+            mode,
+            open_commitments: 0,
+            commitments: commitments(),
+            bids: bids(),
         }
     }
 
     #[payable]
     pub fn bid(&mut self) -> Promise {
-        // Assert the auction is still ongoing
         require!(
-            env::block_timestamp() < self.auction_end_time.into(),
-            "Auction has ended"
+            matches!(self.mode, AuctionMode::Open),
+            "Bids must be committed with commit_bid"
         );
+        self.assert_status(&[AuctionStatus::Active]);
 
         // Current bid
         let bid = env::attached_deposit();
@@ -63,59 +184,205 @@ impl Contract {
         // Last bid
         let Bid {
             bidder: last_bidder,
-            bid: last_bid,
+            ..
         } = self.highest_bid.clone();
+        let last_deposit = self.escrowed_bid();
+
+        // A bid at the buy-now price ends the auction right away
+        if let Some(buy_now_price) = self.buy_now_price.filter(|price| bid >= *price) {
+            return self.buy_now(bidder, bid, buy_now_price, last_bidder, last_deposit);
+        }
 
-        // Check if the deposit is higher than the current bid
-        require!(bid > last_bid, "You must place a higher bid");
+        // Check if the deposit is high enough to outbid the current bid
+        require!(bid >= self.get_minimum_bid(), "You must place a higher bid");
+
+        // A proxy bidder in the lead answers bids up to their maximum
+        if bidder != last_bidder && self.max_bid.is_some_and(|leading_max| bid <= leading_max) {
+            return self.outbid_by_proxy(bidder, bid);
+        }
 
         // Update the highest bid
+        AuctionEvent::BidPlaced {
+            bidder: &bidder,
+            amount: bid,
+        }
+        .emit();
+        self.record_bid(&bidder, bid);
         self.highest_bid = Bid { bidder, bid };
+        self.max_bid = None;
+
+        // Give other bidders time to respond to a last-minute bid
+        self.extend_auction_end_time();
 
         // Transfer tokens back to the last bidder
-        Promise::new(last_bidder).transfer(last_bid)
+        self.refund(last_bidder, last_deposit)
     }
 
     pub fn claim(&mut self) -> Promise {
-        assert!(
-            env::block_timestamp() > self.auction_end_time.into(),
+        self.assert_status(&[AuctionStatus::Ended]);
+        require!(
+            env::block_timestamp() > self.settlement_time(),
             "Auction has not ended yet"
         );
+        self.assert_reserve_can_be_settled();
 
-        assert!(!self.claimed, "Auction has already been claimed");
+        self.status = AuctionStatus::Settling;
 
-        self.claimed = true;
-
-        // Transfer tokens to the auctioneer
-        let transfer_to_auctioneer = Promise::new(self.auctioneer.clone()).transfer(self.highest_bid.bid);
+        // Without bids the NFT goes back to the auctioneer
+        if !self.has_bids() {
+            return self.start_settlement();
+        }
 
-        // Transfer the NFT to the highest bidder
-        let transfer_nft = nft_contract::ext(self.nft_contract.clone())
-            .with_static_gas(Gas::from_tgas(30))
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .nft_transfer(self.highest_bid.bidder.clone(), self.token_id.clone());
+        // If the reserve was not met the NFT goes back to the auctioneer
+        // and the highest bidder gets their bid back
+        if !self.reserve_met() {
+            AuctionEvent::ReserveNotMet {
+                bidder: &self.highest_bid.bidder,
+                amount: self.highest_bid.bid,
+            }
+            .emit();
+            return self.start_settlement();
+        }
 
-        // Chain the promises
-        transfer_to_auctioneer.then(transfer_nft)
+        self.settle()
     }
 
     pub fn get_highest_bid(&self) -> Bid {
         self.highest_bid.clone()
     }
 
+    pub fn get_start_time(&self) -> U64 {
+        self.start_time
+    }
+
     pub fn get_auction_end_time(&self) -> U64 {
         self.auction_end_time
     }
 
+    pub fn get_original_end_time(&self) -> U64 {
+        self.original_end_time
+    }
+
     pub fn get_auction_info(&self) -> &Contract {
         self
     }
 }
 
+impl Contract {
+    // Pays the auctioneer and sends the NFT to the highest bidder
+    fn settle(&mut self) -> Promise {
+        let price = self.get_clearing_price();
+        AuctionEvent::AuctionClaimed {
+            winner: &self.highest_bid.bidder,
+            price,
+        }
+        .emit();
+        let settlement = self.start_settlement();
+
+        // The winner gets back what they escrowed over the price, in
+        // second-price auctions or when they placed a proxy bid
+        let escrowed_bid = self.escrowed_bid();
+        if price < escrowed_bid {
+            let change = escrowed_bid.saturating_sub(price);
+            return settlement.and(self.refund(self.highest_bid.bidder.clone(), change));
+        }
+
+        settlement
+    }
+
+    // Ends the auction with a bid at the buy-now price, whatever was
+    // attached on top of the price goes back to the bidder
+    pub(crate) fn buy_now(
+        &mut self,
+        bidder: AccountId,
+        bid: NearToken,
+        buy_now_price: NearToken,
+        last_bidder: AccountId,
+        last_bid: NearToken,
+    ) -> Promise {
+        AuctionEvent::BidPlaced {
+            bidder: &bidder,
+            amount: buy_now_price,
+        }
+        .emit();
+        self.record_bid(&bidder, buy_now_price);
+        self.highest_bid = Bid {
+            bidder: bidder.clone(),
+            bid: buy_now_price,
+        };
+        self.max_bid = None;
+        self.auction_end_time = U64(env::block_timestamp());
+        self.status = AuctionStatus::Settling;
+
+        let mut refunds = self.refund(last_bidder, last_bid);
+        if bid > buy_now_price {
+            refunds = refunds.and(self.refund(bidder, bid.saturating_sub(buy_now_price)));
+        }
+
+        refunds.and(self.settle())
+    }
+
+    // Pushes the end of the auction forward when a bid arrives within the
+    // extension window, up to `max_extension` past the original end time
+    pub(crate) fn extend_auction_end_time(&mut self) {
+        let (Some(window), Some(duration)) = (self.extension_window, self.extension_duration)
+        else {
+            return;
+        };
+
+        let now = env::block_timestamp();
+        let end_time: u64 = self.auction_end_time.into();
+        if end_time.saturating_sub(now) >= window.into() {
+            return;
+        }
+
+        let mut new_end_time = now.saturating_add(duration.into());
+        if let Some(max_extension) = self.max_extension {
+            let latest_end_time =
+                u64::from(self.original_end_time).saturating_add(max_extension.into());
+            new_end_time = new_end_time.min(latest_end_time);
+        }
+
+        if new_end_time > end_time {
+            self.auction_end_time = U64(new_end_time);
+            AuctionEvent::AuctionExtended {
+                end_time: self.auction_end_time,
+            }
+            .emit();
+        }
+    }
+
+    // Returns tokens to a bidder
+    pub(crate) fn refund(&self, account_id: AccountId, amount: NearToken) -> Promise {
+        AuctionEvent::BidRefunded {
+            bidder: &account_id,
+            amount,
+        }
+        .emit();
+        Promise::new(account_id).transfer(amount)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{serde_json, testing_env, PromiseError};
+
     use super::*;
 
+    // The auctioneer transfers the NFT to the contract with `nft_transfer_call`
+    fn receive_nft(contract: &mut Contract, msg: &str) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("nft.near".parse().unwrap())
+            .build());
+        contract.nft_on_transfer(
+            "auctioneer.near".parse().unwrap(),
+            "auctioneer.near".parse().unwrap(),
+            "1".to_string(),
+            msg.to_string(),
+        );
+    }
+
     #[test]
     fn init_contract() {
         let end_time: U64 = U64::from(1000);
@@ -123,10 +390,18 @@ mod tests {
         let nft_contract: AccountId = "nft.near".parse().unwrap();
         let token_id: TokenId = "1".to_string();
         let contract = Contract::init(
-            end_time.clone(),
+            end_time,
             alice.clone(),
             nft_contract.clone(),
             token_id.clone(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
 
         let default_bid = contract.get_highest_bid();
@@ -138,6 +413,451 @@ mod tests {
         assert_eq!(auction_info.auctioneer, alice);
         assert_eq!(auction_info.nft_contract, nft_contract);
         assert_eq!(auction_info.token_id, token_id);
-        assert_eq!(auction_info.claimed, false);
+        assert!(!auction_info.claimed);
+        assert_eq!(contract.get_status(), AuctionStatus::Pending);
+    }
+
+    #[test]
+    fn buy_now_price_ends_the_auction() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(NearToken::from_near(5)),
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        let bob: AccountId = "bob.near".parse().unwrap();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(bob.clone())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_near(6))
+            .build());
+        let _ = contract.bid();
+
+        // Bob pays the buy-now price and the auction is settled
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, bob);
+        assert_eq!(highest_bid.bid, NearToken::from_near(5));
+        assert_eq!(contract.get_auction_end_time(), U64::from(500));
+        assert_eq!(contract.get_status(), AuctionStatus::Settling);
+        assert_eq!(contract.get_bid_count(), 1);
+
+        let events: Vec<String> = get_logs()
+            .iter()
+            .map(|log| {
+                let event: serde_json::Value =
+                    serde_json::from_str(log.strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+                event["event"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                "bid_placed",
+                "bid_refunded",
+                "bid_refunded",
+                "auction_claimed"
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "A buy-now price cannot be set with a hidden reserve")]
+    fn buy_now_price_rejects_hidden_reserve() {
+        let reserve_hash =
+            env::sha256(format!("{}:salt", NearToken::from_near(5).as_yoctonear()).as_bytes());
+        Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            Some(ReservePrice::Hidden(reserve_hash.into())),
+            None,
+            Some(NearToken::from_near(5)),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has been cancelled")]
+    fn cancelled_auctions_reject_bids() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .block_timestamp(100)
+            .build());
+        let _ = contract.cancel();
+        assert!(contract.is_cancelled());
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(200)
+            .attached_deposit(NearToken::from_near(1))
+            .build());
+        let _ = contract.bid();
+    }
+
+    #[test]
+    fn failed_cancel_can_be_retried() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .block_timestamp(100)
+            .build());
+        let _ = contract.cancel();
+        assert!(contract.is_cancelled());
+
+        // The NFT could not be returned, the auction is back on
+        assert!(!contract.resolve_cancel(Err(PromiseError::Failed)));
+        assert!(!contract.is_cancelled());
+        assert_eq!(contract.get_status(), AuctionStatus::Active);
+
+        let _ = contract.cancel();
+        assert!(contract.resolve_cancel(Ok(())));
+        assert!(contract.is_cancelled());
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has committed bids")]
+    fn committed_bids_block_the_cancellation() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(AuctionMode::Sealed {
+                reveal_end_time: U64::from(2000),
+                second_price: false,
+            }),
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(100)
+            .attached_deposit(NearToken::from_near(1))
+            .build());
+        contract.commit_bid(vec![0; 32].into());
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .block_timestamp(200)
+            .build());
+        let _ = contract.cancel();
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction has not started yet")]
+    fn bids_wait_for_the_start_time() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(U64::from(500)),
+        );
+        receive_nft(&mut contract, "");
+        assert_eq!(contract.get_status(), AuctionStatus::Pending);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(400)
+            .attached_deposit(NearToken::from_near(1))
+            .build());
+        let _ = contract.bid();
+    }
+
+    #[test]
+    fn proxy_bids_are_raised_up_to_the_maximum() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            Some(BidIncrement::Absolute(NearToken::from_near(1))),
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        // Bob is willing to pay up to 10 NEAR but only bids the minimum
+        let bob: AccountId = "bob.near".parse().unwrap();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(bob.clone())
+            .block_timestamp(100)
+            .attached_deposit(NearToken::from_near(10))
+            .build());
+        let _ = contract.proxy_bid();
+        assert_eq!(
+            contract.get_highest_bid().bid,
+            NearToken::from_yoctonear(NearToken::from_near(1).as_yoctonear() + 1)
+        );
+
+        // Carol's bid is answered by Bob's proxy, one increment over it
+        let carol: AccountId = "carol.near".parse().unwrap();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(carol.clone())
+            .block_timestamp(200)
+            .attached_deposit(NearToken::from_near(4))
+            .build());
+        let _ = contract.bid();
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, bob);
+        assert_eq!(highest_bid.bid, NearToken::from_near(5));
+        assert_eq!(contract.get_bid_count(), 3);
+
+        // A higher maximum takes the lead one increment over Bob's maximum
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(carol.clone())
+            .block_timestamp(300)
+            .attached_deposit(NearToken::from_near(20))
+            .build());
+        let _ = contract.proxy_bid();
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, carol);
+        assert_eq!(highest_bid.bid, NearToken::from_near(11));
+        assert_eq!(contract.escrowed_bid(), NearToken::from_near(20));
+
+        // Carol's own bid is not answered by her proxy
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(carol.clone())
+            .block_timestamp(400)
+            .attached_deposit(NearToken::from_near(15))
+            .build());
+        let _ = contract.bid();
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, carol);
+        assert_eq!(highest_bid.bid, NearToken::from_near(15));
+        assert_eq!(contract.escrowed_bid(), NearToken::from_near(15));
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has not received the NFT yet")]
+    fn bids_wait_for_the_nft() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_near(1))
+            .build());
+        let _ = contract.bid();
+    }
+
+    #[test]
+    fn nft_transfer_msg_configures_the_auction() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(
+            &mut contract,
+            r#"{"end_time":"2000","reserve_price":{"public":"1000000000000000000000000"}}"#,
+        );
+
+        assert_eq!(contract.get_status(), AuctionStatus::Active);
+        assert_eq!(contract.get_auction_end_time(), U64::from(2000));
+        assert_eq!(contract.get_reserve_price(), Some(NearToken::from_near(1)));
+    }
+
+    #[test]
+    fn activate_checks_the_nft_owner() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        // The NFT still belongs to the auctioneer
+        assert!(!contract.resolve_activate(Ok(Some(JsonToken {
+            token_id: "1".to_string(),
+            owner_id: "auctioneer.near".parse().unwrap(),
+        }))));
+        assert!(!contract.is_active());
+
+        // The NFT was transferred to the contract
+        assert!(contract.resolve_activate(Ok(Some(JsonToken {
+            token_id: "1".to_string(),
+            owner_id: env::current_account_id(),
+        }))));
+        assert!(contract.is_active());
+    }
+
+    #[test]
+    fn failed_nft_delivery_can_be_retried() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_near(1))
+            .build());
+        let _ = contract.bid();
+
+        testing_env!(VMContextBuilder::new().block_timestamp(1500).build());
+        assert_eq!(contract.get_status(), AuctionStatus::Ended);
+        let _ = contract.claim();
+        let settlement = contract.get_settlement().unwrap();
+        assert_eq!(settlement.proceeds, LegStatus::Pending);
+        assert_eq!(settlement.nft, LegStatus::Pending);
+        assert_eq!(contract.get_status(), AuctionStatus::Settling);
+
+        // The auctioneer is paid but the NFT transfer fails
+        contract.resolve_proceeds(Ok(()));
+        contract.resolve_nft_delivery(Err(PromiseError::Failed));
+        let settlement = contract.get_settlement().unwrap();
+        assert_eq!(settlement.proceeds, LegStatus::Completed);
+        assert_eq!(settlement.nft, LegStatus::Failed);
+        assert_eq!(contract.get_status(), AuctionStatus::Failed);
+
+        // Only the NFT transfer is sent again
+        let _ = contract.retry_settlement();
+        let settlement = contract.get_settlement().unwrap();
+        assert_eq!(settlement.proceeds, LegStatus::Completed);
+        assert_eq!(settlement.nft, LegStatus::Pending);
+
+        contract.resolve_nft_delivery(Ok(()));
+        assert_eq!(contract.get_status(), AuctionStatus::Settled);
+    }
+
+    #[test]
+    fn claims_without_bids_return_the_nft() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new().block_timestamp(1500).build());
+        let _ = contract.claim();
+
+        assert!(contract.get_auction_info().claimed);
+
+        // There is nothing to pay, only the NFT goes back to the auctioneer
+        let settlement = contract.get_settlement().unwrap();
+        assert_eq!(settlement.proceeds, LegStatus::Completed);
+        assert_eq!(settlement.nft, LegStatus::Pending);
+        assert!(get_logs().is_empty());
+
+        contract.resolve_nft_delivery(Ok(()));
+        assert_eq!(contract.get_status(), AuctionStatus::Settled);
     }
 }
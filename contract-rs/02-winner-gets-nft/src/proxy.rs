@@ -0,0 +1,110 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::{env, near, require, AccountId, NearToken, Promise};
+
+use crate::{AuctionEvent, AuctionMode, AuctionStatus, Bid, Contract, ContractExt, ReservePrice};
+
+#[near]
+impl Contract {
+    // Bidders attach the most they are willing to pay, the contract only bids
+    // what is needed to take the lead and raises the bid for them when
+    // someone else bids, up to that maximum
+    #[payable]
+    pub fn proxy_bid(&mut self) -> Promise {
+        require!(
+            matches!(self.mode, AuctionMode::Open),
+            "Bids must be committed with commit_bid"
+        );
+        self.assert_status(&[AuctionStatus::Active]);
+
+        let max_bid = env::attached_deposit();
+        let bidder = env::predecessor_account_id();
+        let Bid {
+            bidder: last_bidder,
+            ..
+        } = self.highest_bid.clone();
+        let last_deposit = self.escrowed_bid();
+
+        // A maximum at the buy-now price ends the auction right away
+        if let Some(buy_now_price) = self.buy_now_price.filter(|price| max_bid >= *price) {
+            return self.buy_now(bidder, max_bid, buy_now_price, last_bidder, last_deposit);
+        }
+
+        require!(
+            max_bid >= self.get_minimum_bid(),
+            "You must place a higher bid"
+        );
+
+        // The highest bidder can raise their maximum without raising their bid
+        if bidder == last_bidder {
+            require!(
+                max_bid > last_deposit,
+                "The new maximum must be higher than the current one"
+            );
+            self.max_bid = Some(max_bid);
+            return self.refund(bidder, last_deposit);
+        }
+
+        // The maximum of the proxy bidder in the lead is not beaten
+        if self
+            .max_bid
+            .is_some_and(|leading_max| max_bid <= leading_max)
+        {
+            return self.outbid_by_proxy(bidder, max_bid);
+        }
+
+        // Take the lead one increment over what the last bidder could pay,
+        // and at least at a public reserve
+        let mut bid = self.minimum_bid_over(last_deposit);
+        if let Some(ReservePrice::Public(reserve)) = &self.reserve_price {
+            bid = bid.max(*reserve);
+        }
+        let bid = bid.min(max_bid);
+
+        AuctionEvent::BidPlaced {
+            bidder: &bidder,
+            amount: bid,
+        }
+        .emit();
+        self.record_bid(&bidder, bid);
+        self.highest_bid = Bid { bidder, bid };
+        self.max_bid = Some(max_bid);
+
+        // Give other bidders time to respond to a last-minute bid
+        self.extend_auction_end_time();
+
+        self.refund(last_bidder, last_deposit)
+    }
+}
+
+impl Contract {
+    // What the contract holds for the highest bid, the whole maximum of a proxy bid
+    pub(crate) fn escrowed_bid(&self) -> NearToken {
+        self.max_bid.unwrap_or(self.highest_bid.bid)
+    }
+
+    // A bid that does not beat the maximum of the proxy bidder in the lead is
+    // recorded, then the proxy raises its bid over it and the bid is refunded
+    pub(crate) fn outbid_by_proxy(&mut self, bidder: AccountId, bid: NearToken) -> Promise {
+        AuctionEvent::BidPlaced {
+            bidder: &bidder,
+            amount: bid,
+        }
+        .emit();
+        self.record_bid(&bidder, bid);
+
+        let leading_max = self.max_bid.unwrap();
+        let proxy_bid = self.minimum_bid_over(bid).min(leading_max);
+        let leader = self.highest_bid.bidder.clone();
+        AuctionEvent::BidPlaced {
+            bidder: &leader,
+            amount: proxy_bid,
+        }
+        .emit();
+        self.record_bid(&leader, proxy_bid);
+        self.highest_bid.bid = proxy_bid;
+
+        self.extend_auction_end_time();
+
+        self.refund(bidder, bid)
+    }
+}
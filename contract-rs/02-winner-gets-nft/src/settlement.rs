@@ -0,0 +1,164 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::{env, near, require, Gas, NearToken, Promise, PromiseError};
+
+use crate::{nft_contract, AuctionStatus, Contract, ContractExt};
+
+const NFT_TRANSFER_GAS: Gas = Gas::from_tgas(30);
+const SETTLEMENT_CALLBACK_GAS: Gas = Gas::from_tgas(5);
+
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LegStatus {
+    // The transfer was sent and its result is not known yet
+    Pending,
+    Completed,
+    // The transfer failed and can be sent again with `retry_settlement`
+    Failed,
+}
+
+// Once claimed, the auction is settled in two independent legs: the
+// proceeds go to the auctioneer and the NFT to the winner. If the reserve
+// was not met the bid goes back to the bidder and the NFT to the auctioneer,
+// without bids there is nothing to pay and only the NFT is returned
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct Settlement {
+    pub proceeds: LegStatus,
+    pub nft: LegStatus,
+}
+
+#[near]
+impl Contract {
+    // Sends again the legs of the settlement that failed
+    pub fn retry_settlement(&mut self) -> Promise {
+        let Some(settlement) = &self.settlement else {
+            env::panic_str("Auction has not been claimed yet");
+        };
+        let retry_proceeds = settlement.proceeds == LegStatus::Failed;
+        let retry_nft = settlement.nft == LegStatus::Failed;
+        require!(
+            retry_proceeds || retry_nft,
+            "There is no failed settlement to retry"
+        );
+        self.status = AuctionStatus::Settling;
+
+        match (retry_proceeds, retry_nft) {
+            (true, true) => {
+                let proceeds = self.pay_proceeds();
+                proceeds.and(self.deliver_nft())
+            }
+            (true, false) => self.pay_proceeds(),
+            _ => self.deliver_nft(),
+        }
+    }
+
+    #[private]
+    pub fn resolve_proceeds(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        let settlement = self.settlement.as_mut().unwrap();
+        settlement.proceeds = leg_status(&transfer_result);
+        self.update_settlement_status();
+        transfer_result.is_ok()
+    }
+
+    #[private]
+    pub fn resolve_nft_delivery(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        let settlement = self.settlement.as_mut().unwrap();
+        settlement.nft = leg_status(&transfer_result);
+        self.update_settlement_status();
+        transfer_result.is_ok()
+    }
+
+    pub fn get_settlement(&self) -> Option<Settlement> {
+        self.settlement.clone()
+    }
+}
+
+impl Contract {
+    // Sends both legs of the settlement
+    pub(crate) fn start_settlement(&mut self) -> Promise {
+        self.claimed = true;
+        if !self.has_bids() {
+            self.settlement = Some(Settlement {
+                proceeds: LegStatus::Completed,
+                nft: LegStatus::Pending,
+            });
+            return self.deliver_nft();
+        }
+
+        self.settlement = Some(Settlement {
+            proceeds: LegStatus::Pending,
+            nft: LegStatus::Pending,
+        });
+        let proceeds = self.pay_proceeds();
+        proceeds.and(self.deliver_nft())
+    }
+
+    // The auction is settled once both legs completed, and failed when a leg
+    // failed and the other one is not pending anymore
+    fn update_settlement_status(&mut self) {
+        let settlement = self.settlement.as_ref().unwrap();
+        let legs = [settlement.proceeds, settlement.nft];
+        self.status = if legs.contains(&LegStatus::Pending) {
+            AuctionStatus::Settling
+        } else if legs.contains(&LegStatus::Failed) {
+            AuctionStatus::Failed
+        } else {
+            AuctionStatus::Settled
+        };
+    }
+
+    // Until somebody bids the contract is the highest bidder
+    pub(crate) fn has_bids(&self) -> bool {
+        self.highest_bid.bidder != env::current_account_id()
+    }
+
+    fn pay_proceeds(&mut self) -> Promise {
+        self.settlement.as_mut().unwrap().proceeds = LegStatus::Pending;
+
+        let payment = if self.reserve_met() {
+            Promise::new(self.auctioneer.clone()).transfer(self.get_clearing_price())
+        } else {
+            self.refund(self.highest_bid.bidder.clone(), self.escrowed_bid())
+        };
+
+        payment.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(SETTLEMENT_CALLBACK_GAS)
+                .resolve_proceeds(),
+        )
+    }
+
+    fn deliver_nft(&mut self) -> Promise {
+        self.settlement.as_mut().unwrap().nft = LegStatus::Pending;
+
+        let receiver_id = if self.has_bids() && self.reserve_met() {
+            self.highest_bid.bidder.clone()
+        } else {
+            self.auctioneer.clone()
+        };
+
+        nft_contract::ext(self.nft_contract.clone())
+            .with_static_gas(NFT_TRANSFER_GAS)
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .nft_transfer(receiver_id, self.token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(SETTLEMENT_CALLBACK_GAS)
+                    .resolve_nft_delivery(),
+            )
+    }
+}
+
+fn leg_status(transfer_result: &Result<(), PromiseError>) -> LegStatus {
+    match transfer_result {
+        Ok(()) => LegStatus::Completed,
+        Err(_) => LegStatus::Failed,
+    }
+}
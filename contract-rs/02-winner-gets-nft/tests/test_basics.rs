@@ -54,11 +54,11 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .await?
         .assert_success();
 
-    // Mint NFT
+    // Mint NFT to the auctioneer
     const TOKEN_ID: &str = "1";
     let request_payload = json!({
         "token_id": TOKEN_ID,
-        "receiver_id": contract.account_id(),
+        "receiver_id": auctioneer.account_id(),
         "token_metadata": {
             "title": "LEEROYYYMMMJENKINSSS",
             "description": "Alright time's up, let's do this.",
@@ -91,6 +91,30 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .await?
         .assert_success();
 
+    // Alice cannot bid before the contract holds the NFT
+    contract
+        .call_function("bid", ())
+        .transaction()
+        .deposit(NearToken::from_near(1))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    // The auctioneer transfers the NFT to the contract, which starts the auction
+    nft_contract
+        .call_function(
+            "nft_transfer_call",
+            json!({"receiver_id": contract.account_id(), "token_id": TOKEN_ID, "msg": ""}),
+        )
+        .transaction()
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
     // Alice makes first bid
     contract
         .call_function("bid", ())
@@ -175,7 +199,7 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
     contract
         .call_function("claim", ())
         .transaction()
-        .gas(NearGas::from_tgas(40))
+        .gas(NearGas::from_tgas(100))
         .with_signer(auctioneer.account_id().clone(), signer.clone())
         .send_to(&sandbox_network)
         .await?
@@ -206,6 +230,28 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         "token owner is not the highest bidder"
     );
 
+    // Both legs of the settlement went through
+    let settlement: serde_json::Value = contract
+        .call_function("get_settlement", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(
+        settlement,
+        json!({"proceeds": "completed", "nft": "completed"})
+    );
+
+    // There is nothing to retry
+    contract
+        .call_function("retry_settlement", ())
+        .transaction()
+        .gas(NearGas::from_tgas(100))
+        .with_signer(bob.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
     // Auctioneer tries to claim the auction again
     contract
         .call_function("claim", ())
@@ -0,0 +1,33 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::U128;
+use near_sdk::store::LookupMap;
+use near_sdk::{near, AccountId};
+
+// A price oracle for testing the auction, anyone can set the rates.
+// Rates are the value of one unit of a token, scaled by 10^12
+#[near(contract_state)]
+pub struct Contract {
+    rates: LookupMap<AccountId, U128>,
+}
+
+impl Default for Contract {
+    fn default() -> Self {
+        Self {
+            rates: LookupMap::new(b"r"),
+        }
+    }
+}
+
+#[near]
+impl Contract {
+    pub fn set_rate(&mut self, ft_contract: AccountId, rate: U128) {
+        self.rates.insert(ft_contract, rate);
+    }
+
+    pub fn get_rates(&self, ft_contracts: Vec<AccountId>) -> Vec<Option<U128>> {
+        ft_contracts
+            .iter()
+            .map(|ft_contract| self.rates.get(ft_contract).copied())
+            .collect()
+    }
+}
@@ -0,0 +1,69 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::{env, near, require, Gas, NearToken, Promise, PromiseError};
+
+use crate::{nft_contract, AuctionEvent, AuctionStatus, Contract, ContractExt};
+
+const NFT_TRANSFER_GAS: Gas = Gas::from_tgas(30);
+const CANCEL_CALLBACK_GAS: Gas = Gas::from_tgas(5);
+
+#[near]
+impl Contract {
+    // The auctioneer can call the auction off and get the NFT back as long
+    // as nobody has bid, the contract is still the highest bidder
+    pub fn cancel(&mut self) -> Promise {
+        require!(
+            env::predecessor_account_id() == self.auctioneer,
+            "Only the auctioneer can cancel the auction"
+        );
+        // An auction that holds the NFT can be cancelled before it starts
+        self.assert_status(&[
+            AuctionStatus::Pending,
+            AuctionStatus::Active,
+            AuctionStatus::Ended,
+        ]);
+        require!(
+            self.status != AuctionStatus::Pending,
+            "The auction has not received the NFT yet"
+        );
+        require!(!self.has_bids(), "The auction already has bids");
+        // Sealed bids are only known once revealed, so the auction cannot be
+        // called off while deposits are committed
+        require!(
+            self.open_commitments == 0,
+            "The auction has committed bids"
+        );
+
+        self.status = AuctionStatus::Cancelled;
+        AuctionEvent::AuctionCancelled {
+            auctioneer: &self.auctioneer,
+        }
+        .emit();
+
+        nft_contract::ext(self.nft_contract.clone())
+            .with_static_gas(NFT_TRANSFER_GAS)
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .nft_transfer(self.auctioneer.clone(), self.token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(CANCEL_CALLBACK_GAS)
+                    .resolve_cancel(),
+            )
+    }
+
+    // If the NFT could not be returned the auction goes back to active,
+    // so the auctioneer can cancel it again
+    #[private]
+    pub fn resolve_cancel(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        if transfer_result.is_err() {
+            self.status = AuctionStatus::Active;
+        }
+        transfer_result.is_ok()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status == AuctionStatus::Cancelled
+    }
+}
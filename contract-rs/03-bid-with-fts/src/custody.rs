@@ -0,0 +1,108 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde_json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::oracle::accepted_fts;
+use crate::{AuctionMode, AuctionStatus, Contract, ContractExt, TokenId};
+
+// The `msg` of `nft_transfer_call` when the auctioneer hands over the NFT,
+// any field that is set replaces the value passed to `init`
+#[near(serializers = [json])]
+#[derive(Default)]
+pub struct AuctionMsg {
+    pub end_time: Option<U64>,
+    pub starting_price: Option<U128>,
+    pub ft_contract: Option<AccountId>,
+}
+
+#[near]
+impl Contract {
+    // Called by the NFT contract when the auctioneer transfers the NFT with
+    // `nft_transfer_call`, the auction takes bids from then on
+    #[allow(unused_variables)]
+    pub fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> bool {
+        require!(
+            env::predecessor_account_id() == self.nft_contract && token_id == self.token_id,
+            "This is not the NFT being auctioned"
+        );
+        require!(
+            previous_owner_id == self.auctioneer,
+            "Only the auctioneer can transfer the NFT"
+        );
+        require!(
+            self.status == AuctionStatus::Pending,
+            "The NFT has already been received"
+        );
+
+        let AuctionMsg {
+            end_time,
+            starting_price,
+            ft_contract,
+        } = if msg.is_empty() {
+            AuctionMsg::default()
+        } else {
+            serde_json::from_str(&msg)
+                .unwrap_or_else(|_| env::panic_str("The msg is not a valid auction config"))
+        };
+
+        if let Some(end_time) = end_time {
+            require!(
+                end_time.0 > env::block_timestamp(),
+                "The auction must end in the future"
+            );
+            require!(
+                end_time > self.start_time,
+                "The auction must start before it ends"
+            );
+            if let AuctionMode::Sealed {
+                reveal_end_time, ..
+            } = self.mode
+            {
+                require!(
+                    reveal_end_time > end_time,
+                    "The reveal phase must end after the auction"
+                );
+            }
+            self.auction_end_time = end_time;
+            self.original_end_time = end_time;
+        }
+        if let Some(starting_price) = starting_price {
+            Self::assert_buy_now_price(self.buy_now_price, starting_price);
+            self.starting_price = starting_price;
+            self.highest_bid.bid = starting_price;
+        }
+        if let Some(ft_contract) = ft_contract {
+            // The other accepted tokens are kept, the new base token goes first
+            let other_fts = self.accepted_fts[1..]
+                .iter()
+                .map(|accepted_ft| accepted_ft.ft_contract.clone())
+                .collect();
+            self.accepted_fts = accepted_fts(ft_contract.clone(), other_fts);
+            self.highest_bid.ft_contract = ft_contract.clone();
+            self.ft_contract = ft_contract;
+        }
+
+        self.status = AuctionStatus::Active;
+
+        // Keep the NFT
+        false
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_buy_now_price(buy_now_price: Option<U128>, starting_price: U128) {
+        if let Some(buy_now_price) = buy_now_price {
+            require!(
+                buy_now_price > starting_price,
+                "The buy-now price must be higher than the starting price"
+            );
+        }
+    }
+}
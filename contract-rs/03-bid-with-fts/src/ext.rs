@@ -7,13 +7,20 @@ use crate::TokenId;
 // FT interface for cross-contract calls
 #[ext_contract(ft_contract)]
 #[allow(dead_code)]
-trait FT {
+trait Ft {
     fn ft_transfer(&self, receiver_id: AccountId, amount: U128);
 }
 
 // NFT interface for cross-contract calls
 #[ext_contract(nft_contract)]
 #[allow(dead_code)]
-trait NFT {
+trait Nft {
     fn nft_transfer(&self, receiver_id: AccountId, token_id: TokenId);
 }
+
+// Price oracle interface for cross-contract calls
+#[ext_contract(price_oracle)]
+#[allow(dead_code)]
+trait PriceOracle {
+    fn get_rates(&self, ft_contracts: Vec<AccountId>) -> Vec<Option<U128>>;
+}
@@ -0,0 +1,55 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near, AccountId};
+
+use crate::{Contract, ContractExt};
+
+const DEFAULT_BIDS_LIMIT: u32 = 50;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct BidRecord {
+    pub bidder: AccountId,
+    pub amount: U128,
+    pub ft_contract: AccountId,
+    pub referral: Option<AccountId>,
+    pub timestamp: U64,
+    pub block_height: U64,
+}
+
+#[near]
+impl Contract {
+    // Bids in the order they were placed, starting at `from_index`
+    pub fn get_bids(&self, from_index: Option<u32>, limit: Option<u32>) -> Vec<BidRecord> {
+        self.bids
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(DEFAULT_BIDS_LIMIT) as usize)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_bid_count(&self) -> u32 {
+        self.bids.len()
+    }
+}
+
+impl Contract {
+    // Every accepted bid is kept, even after it has been outbid
+    pub(crate) fn record_bid(
+        &mut self,
+        bidder: &AccountId,
+        amount: U128,
+        ft_contract: AccountId,
+        referral: Option<AccountId>,
+    ) {
+        self.bids.push(BidRecord {
+            bidder: bidder.clone(),
+            amount,
+            ft_contract,
+            referral,
+            timestamp: U64(env::block_timestamp()),
+            block_height: U64(env::block_height()),
+        });
+    }
+}
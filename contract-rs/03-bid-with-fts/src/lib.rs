@@ -1,35 +1,118 @@
 // Find all our documentation at https://docs.near.org
 use near_sdk::json_types::{U128, U64};
-use near_sdk::{env, near, require, AccountId, Gas, NearToken, PanicOnDefault};
+use near_sdk::serde_json::{self, Value};
+use near_sdk::store::{LookupMap, Vector};
+use near_sdk::{env, near, require, AccountId, BorshStorageKey, PanicOnDefault};
 
+pub mod cancel;
+pub mod custody;
+pub mod events;
 pub mod ext;
+pub mod history;
+pub mod oracle;
+pub mod proxy;
+pub mod refunds;
+pub mod sealed;
+pub mod settlement;
+pub mod status;
+pub use crate::custody::*;
+pub use crate::events::*;
 pub use crate::ext::*;
+pub use crate::history::*;
+pub use crate::oracle::*;
+pub use crate::sealed::*;
+pub use crate::settlement::*;
+pub use crate::status::*;
 
 #[near(serializers = [json, borsh])]
 #[derive(Clone)]
 pub struct Bid {
     pub bidder: AccountId,
     pub bid: U128,
+    pub ft_contract: AccountId,
 }
 
 pub type TokenId = String;
 
+// The `msg` of `ft_transfer_call` when bidding in open auctions, a `msg`
+// that is not a JSON object, e.g. an empty one, bids all the tokens transferred
+#[near(serializers = [json])]
+#[derive(Default)]
+pub struct BidMsg {
+    // Only this amount of the tokens is bid, the rest is returned
+    pub max_bid: Option<U128>,
+    // Account that brought the bidder to the auction, kept with the bid
+    pub referral: Option<AccountId>,
+    // The tokens bid are a maximum, the contract only bids what is needed
+    // to stay ahead of other bidders
+    #[serde(default)]
+    pub proxy: bool,
+}
+
+#[near(serializers = [borsh])]
+#[derive(BorshStorageKey)]
+enum StorageKey {
+    Commitments,
+    Bids,
+    PendingRefunds,
+}
+
 #[near(contract_state, serializers = [json, borsh])]
 #[derive(PanicOnDefault)]
 pub struct Contract {
     highest_bid: Bid,
+    // The maximum escrowed by the highest bidder when they placed a proxy bid
+    max_bid: Option<U128>,
+    runner_up_bid: Option<Bid>,
+    starting_price: U128,
+    start_time: U64,
     auction_end_time: U64,
+    original_end_time: U64,
+    extension_window: Option<U64>,
+    extension_duration: Option<U64>,
+    max_extension: Option<U64>,
+    buy_now_price: Option<U128>,
     auctioneer: AccountId,
+    status: AuctionStatus,
+    // Set once the settlement starts, the frontends read it from the auction info
     claimed: bool,
+    settlement: Option<Settlement>,
+    // The base token, in which the starting and buy-now prices are set
     ft_contract: AccountId,
+    // Bids in other tokens are compared on the rates of the price oracle
+    price_oracle: Option<AccountId>,
+    accepted_fts: Vec<AcceptedFt>,
     nft_contract: AccountId,
     token_id: TokenId,
+    mode: AuctionMode,
+    // Sealed bids committed and not revealed or withdrawn yet
+    open_commitments: u32,
+    // Collections are not part of the auction info
+    #[serde(skip, default = "commitments")]
+    commitments: LookupMap<AccountId, Commitment>,
+    #[serde(skip, default = "bids")]
+    bids: Vector<BidRecord>,
+    #[serde(skip, default = "pending_refunds")]
+    pending_refunds: LookupMap<(AccountId, AccountId), U128>,
+}
+
+fn commitments() -> LookupMap<AccountId, Commitment> {
+    LookupMap::new(StorageKey::Commitments)
+}
+
+fn bids() -> Vector<BidRecord> {
+    Vector::new(StorageKey::Bids)
+}
+
+fn pending_refunds() -> LookupMap<(AccountId, AccountId), U128> {
+    LookupMap::new(StorageKey::PendingRefunds)
 }
 
 #[near]
 impl Contract {
     #[init]
     #[private] // only callable by the contract's account
+    #[allow(clippy::too_many_arguments)]
     pub fn init(
         end_time: U64,
         auctioneer: AccountId,
@@ -37,96 +120,323 @@ impl Contract {
         nft_contract: AccountId,
         token_id: TokenId,
         starting_price: U128,
+        extension_window: Option<U64>,
+        extension_duration: Option<U64>,
+        max_extension: Option<U64>,
+        mode: Option<AuctionMode>,
+        buy_now_price: Option<U128>,
+        price_oracle: Option<AccountId>,
+        accepted_fts: Option<Vec<AccountId>>,
+        start_time: Option<U64>,
     ) -> Self {
+        // Without a start time the auction takes bids as soon as it holds the NFT
+        let start_time = start_time.unwrap_or(U64(env::block_timestamp()));
+        require!(
+            start_time < end_time,
+            "The auction must start before it ends"
+        );
+        require!(
+            extension_window.is_some() == extension_duration.is_some(),
+            "extension_window and extension_duration must be set together"
+        );
+
+        let mode = mode.unwrap_or(AuctionMode::Open);
+        if let AuctionMode::Sealed {
+            reveal_end_time, ..
+        } = mode
+        {
+            require!(
+                reveal_end_time > end_time,
+                "The reveal phase must end after the auction"
+            );
+            require!(
+                extension_window.is_none(),
+                "Sealed auctions cannot be extended"
+            );
+            require!(
+                buy_now_price.is_none(),
+                "Sealed auctions cannot have a buy-now price"
+            );
+        }
+
+        Self::assert_buy_now_price(buy_now_price, starting_price);
+
+        let accepted_fts =
+            oracle::accepted_fts(ft_contract.clone(), accepted_fts.unwrap_or_default());
+        if accepted_fts.len() > 1 {
+            require!(
+                price_oracle.is_some(),
+                "Accepting several tokens requires a price oracle"
+            );
+            require!(
+                matches!(mode, AuctionMode::Open),
+                "Sealed auctions only accept the base token"
+            );
+        }
+
+        AuctionEvent::AuctionCreated {
+            auctioneer: &auctioneer,
+            end_time,
+        }
+        .emit();
+
         Self {
             highest_bid: Bid {
                 bidder: env::current_account_id(),
                 bid: starting_price,
+                ft_contract: ft_contract.clone(),
             },
+            max_bid: None,
+            runner_up_bid: None,
+            starting_price,
+            start_time,
             auction_end_time: end_time,
+            original_end_time: end_time,
+            extension_window,
+            extension_duration,
+            max_extension,
+            buy_now_price,
             auctioneer,
+            status: AuctionStatus::Pending,
             claimed: false,
+            settlement: None,
             ft_contract,
+            price_oracle,
+            accepted_fts,
             nft_contract,
             token_id,
+            mode,
+            open_commitments: 0,
+            commitments: commitments(),
+            bids: bids(),
+            pending_refunds: pending_refunds(),
         }
     }
 
-    // Users bid by transferring FT tokens
-    #[allow(unused_variables, unused_must_use)]
+    // Users bid by transferring FT tokens, the amount returned is given
+    // back to them by the FT contract
+    #[allow(unused_must_use)]
     pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> U128 {
-        require!(
-            env::block_timestamp() < self.auction_end_time.into(),
-            "Auction has ended"
-        );
+        self.assert_status(&[AuctionStatus::Active]);
 
         let ft = env::predecessor_account_id();
-        require!(ft == self.ft_contract, "The token is not supported");
+        require!(self.is_accepted_ft(&ft), "The token is not supported");
+
+        // In sealed auctions the tokens back a commitment passed in `msg`
+        if let AuctionMode::Sealed { .. } = self.mode {
+            return self.commit_bid(sender_id, amount, msg);
+        }
+
+        let BidMsg {
+            max_bid,
+            referral,
+            proxy,
+        } = match serde_json::from_str(&msg) {
+            Ok(msg @ Value::Object(_)) => serde_json::from_value(msg)
+                .unwrap_or_else(|_| env::panic_str("The msg is not a valid bid")),
+            _ => BidMsg::default(),
+        };
+        let bid = max_bid.map_or(amount, |max_bid| U128(amount.0.min(max_bid.0)));
 
         // Last bid
-        let Bid {
-            bidder: last_bidder,
-            bid: last_bid,
-        } = self.highest_bid.clone();
+        let last_bid = self.highest_bid.clone();
+        let last_deposit = self.escrowed_bid();
+
+        // Bids in different tokens are compared on their value
+        let value = self.value_of(&ft, bid);
+
+        // A bid at the buy-now price ends the auction right away, the tokens
+        // transferred on top of the price are returned by the FT contract
+        let buy_now_value = self
+            .buy_now_price
+            .map(|price| self.value_of(&self.ft_contract, price))
+            .filter(|buy_now_value| value >= *buy_now_value);
+        if let Some(buy_now_value) = buy_now_value {
+            let buy_now_price = self.amount_for_value(&ft, buy_now_value);
+            AuctionEvent::BidPlaced {
+                bidder: &sender_id,
+                amount: buy_now_price,
+                ft_contract: &ft,
+            }
+            .emit();
+            self.record_bid(&sender_id, buy_now_price, ft.clone(), referral);
+            self.highest_bid = Bid {
+                bidder: sender_id,
+                bid: buy_now_price,
+                ft_contract: ft,
+            };
+            self.max_bid = None;
+            self.auction_end_time = U64(env::block_timestamp());
+            self.status = AuctionStatus::Settling;
+
+            if last_bid.bidder != env::current_account_id() {
+                self.refund_ft(last_bid.ft_contract, last_bid.bidder, last_deposit);
+            }
+            self.settle();
+
+            return U128(amount.0 - buy_now_price.0);
+        }
 
         // Check if the deposit is higher than the current bid
-        require!(amount > last_bid, "You must place a higher bid");
+        require!(
+            value > self.value_of(&last_bid.ft_contract, last_bid.bid),
+            "You must place a higher bid"
+        );
+
+        // A proxy bidder in the lead answers bids up to their maximum, all
+        // the tokens are returned by the FT contract
+        if sender_id != last_bid.bidder && self.proxy_value().is_some_and(|max| value <= max) {
+            self.outbid_by_proxy(&sender_id, bid, ft, referral);
+            return amount;
+        }
+
+        if proxy {
+            self.place_proxy_bid(sender_id, bid, ft, referral);
+            return U128(amount.0 - bid.0);
+        }
 
         // Update the highest bid
+        AuctionEvent::BidPlaced {
+            bidder: &sender_id,
+            amount: bid,
+            ft_contract: &ft,
+        }
+        .emit();
+        self.record_bid(&sender_id, bid, ft.clone(), referral);
         self.highest_bid = Bid {
             bidder: sender_id,
-            bid: amount,
+            bid,
+            ft_contract: ft,
         };
+        self.max_bid = None;
+
+        // Give other bidders time to respond to a last-minute bid
+        self.extend_auction_end_time();
 
-        // Transfer FTs back to the last bidder
-        ft_contract::ext(self.ft_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .ft_transfer(last_bidder, last_bid);
+        // Transfer FTs back to the last bidder, unless it is the starting price
+        if last_bid.bidder != env::current_account_id() {
+            self.refund_ft(last_bid.ft_contract, last_bid.bidder, last_deposit);
+        }
 
-        U128(0)
+        // The tokens over `max_bid` are returned by the FT contract
+        U128(amount.0 - bid.0)
     }
 
     #[allow(unused_must_use)]
     pub fn claim(&mut self) {
+        self.assert_status(&[AuctionStatus::Ended]);
         require!(
-            env::block_timestamp() > self.auction_end_time.into(),
+            env::block_timestamp() > self.settlement_time(),
             "Auction has not ended yet"
         );
 
-        require!(!self.claimed, "Auction has been claimed");
-
-        self.claimed = true;
+        self.status = AuctionStatus::Settling;
 
-        // Transfer FTs to the auctioneer
-        ft_contract::ext(self.ft_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .ft_transfer(self.auctioneer.clone(), self.highest_bid.bid);
+        // Without bids the NFT goes back to the auctioneer
+        if !self.has_bids() {
+            self.start_settlement();
+            return;
+        }
 
-        // Transfer the NFT to the highest bidder
-        nft_contract::ext(self.nft_contract.clone())
-            .with_static_gas(Gas::from_tgas(30))
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .nft_transfer(self.highest_bid.bidder.clone(), self.token_id.clone());
+        self.settle();
     }
 
     pub fn get_highest_bid(&self) -> Bid {
         self.highest_bid.clone()
     }
 
+    pub fn get_start_time(&self) -> U64 {
+        self.start_time
+    }
+
     pub fn get_auction_end_time(&self) -> U64 {
         self.auction_end_time
     }
 
+    pub fn get_original_end_time(&self) -> U64 {
+        self.original_end_time
+    }
+
     pub fn get_auction_info(&self) -> &Contract {
         self
     }
 }
 
+impl Contract {
+    // Pays the auctioneer and sends the NFT to the highest bidder
+    #[allow(unused_must_use)]
+    fn settle(&mut self) {
+        let price = self.get_clearing_price();
+        AuctionEvent::AuctionClaimed {
+            winner: &self.highest_bid.bidder,
+            price,
+        }
+        .emit();
+        self.start_settlement();
+
+        // The winner gets back what they escrowed over the price, in
+        // second-price auctions or when they placed a proxy bid
+        let escrowed_bid = self.escrowed_bid();
+        if price < escrowed_bid {
+            let change = U128(escrowed_bid.0 - price.0);
+            self.refund_ft(
+                self.highest_bid.ft_contract.clone(),
+                self.highest_bid.bidder.clone(),
+                change,
+            );
+        }
+    }
+
+    // Pushes the end of the auction forward when a bid arrives within the
+    // extension window, up to `max_extension` past the original end time
+    pub(crate) fn extend_auction_end_time(&mut self) {
+        let (Some(window), Some(duration)) = (self.extension_window, self.extension_duration)
+        else {
+            return;
+        };
+
+        let now = env::block_timestamp();
+        let end_time: u64 = self.auction_end_time.into();
+        if end_time.saturating_sub(now) >= window.into() {
+            return;
+        }
+
+        let mut new_end_time = now.saturating_add(duration.into());
+        if let Some(max_extension) = self.max_extension {
+            let latest_end_time =
+                u64::from(self.original_end_time).saturating_add(max_extension.into());
+            new_end_time = new_end_time.min(latest_end_time);
+        }
+
+        if new_end_time > end_time {
+            self.auction_end_time = U64(new_end_time);
+            AuctionEvent::AuctionExtended {
+                end_time: self.auction_end_time,
+            }
+            .emit();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{serde_json, testing_env, PromiseError};
+
+    // The auctioneer transfers the NFT to the contract with `nft_transfer_call`
+    fn receive_nft(contract: &mut Contract, msg: &str) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("nft.near".parse().unwrap())
+            .build());
+        contract.nft_on_transfer(
+            "auctioneer.near".parse().unwrap(),
+            "auctioneer.near".parse().unwrap(),
+            "1".to_string(),
+            msg.to_string(),
+        );
+    }
 
     #[test]
     fn init_contract() {
@@ -137,12 +447,20 @@ mod tests {
         let token_id: TokenId = "1".to_string();
         let starting_price: U128 = U128(100);
         let contract = Contract::init(
-            end_time.clone(),
+            end_time,
             alice.clone(),
             ft_contract.clone(),
             nft_contract.clone(),
             token_id.clone(),
-            starting_price.clone(),
+            starting_price,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
 
         let default_bid = contract.get_highest_bid();
@@ -155,6 +473,657 @@ mod tests {
         assert_eq!(auction_info.ft_contract, ft_contract);
         assert_eq!(auction_info.nft_contract, nft_contract);
         assert_eq!(auction_info.token_id, token_id);
-        assert_eq!(auction_info.claimed, false);
+        assert!(!auction_info.claimed);
+        assert_eq!(contract.get_status(), AuctionStatus::Pending);
+    }
+
+    #[test]
+    fn late_bid_extends_auction() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            Some(U64::from(100)),
+            Some(U64::from(200)),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(950)
+            .build());
+        contract.ft_on_transfer("alice.near".parse().unwrap(), U128(200), String::new());
+
+        assert_eq!(contract.get_auction_end_time(), U64::from(1150));
+        assert_eq!(contract.get_original_end_time(), U64::from(1000));
+
+        let events: Vec<String> = get_logs()
+            .iter()
+            .map(|log| {
+                let event: serde_json::Value =
+                    serde_json::from_str(log.strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+                event["event"].as_str().unwrap().to_string()
+            })
+            .collect();
+        // The starting price is not refunded to anyone
+        assert_eq!(events, vec!["bid_placed", "auction_extended"]);
+
+        let bids = contract.get_bids(None, None);
+        assert_eq!(bids.len(), 1);
+        assert_eq!(bids[0].amount, U128(200));
+        assert_eq!(bids[0].timestamp, U64::from(950));
+    }
+
+    #[test]
+    fn sealed_bids_are_committed_with_ft_transfers() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            Some(AuctionMode::Sealed {
+                reveal_end_time: U64::from(2000),
+                second_price: true,
+            }),
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        let commitment = env::sha256(format!("{}:{}:salt", alice, 150).as_bytes());
+        let msg = serde_json::json!({ "commitment": Base64VecU8::from(commitment) }).to_string();
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(500)
+            .build());
+        contract.ft_on_transfer(alice.clone(), U128(200), msg);
+        assert_eq!(
+            contract.get_commitment(alice.clone()).unwrap().deposit,
+            U128(200)
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(alice.clone())
+            .block_timestamp(1500)
+            .build());
+        contract.reveal_bid(U128(150), "salt".to_string());
+
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, alice);
+        assert_eq!(highest_bid.bid, U128(150));
+
+        // Being the only bidder, Alice only pays the starting price
+        assert!(contract.get_runner_up_bid().is_none());
+        assert_eq!(contract.get_clearing_price(), U128(100));
+    }
+
+    #[test]
+    fn buy_now_price_ends_the_auction() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            Some(U128(500)),
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(500)
+            .build());
+        let unused = contract.ft_on_transfer(bob.clone(), U128(600), String::new());
+
+        // Bob pays the buy-now price and the auction is settled
+        assert_eq!(unused, U128(100));
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, bob);
+        assert_eq!(highest_bid.bid, U128(500));
+        assert_eq!(contract.get_auction_end_time(), U64::from(500));
+        assert_eq!(contract.get_status(), AuctionStatus::Settling);
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has not received the NFT yet")]
+    fn bids_wait_for_the_nft() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(500)
+            .build());
+        contract.ft_on_transfer("bob.near".parse().unwrap(), U128(200), String::new());
+    }
+
+    #[test]
+    fn nft_transfer_msg_configures_the_auction() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "ft.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(
+            &mut contract,
+            r#"{"end_time":"2000","starting_price":"300","ft_contract":"usdc.near"}"#,
+        );
+
+        assert_eq!(contract.get_status(), AuctionStatus::Active);
+        let auction_info = contract.get_auction_info();
+        assert_eq!(auction_info.auction_end_time, U64::from(2000));
+        assert_eq!(auction_info.ft_contract.as_str(), "usdc.near");
+        assert_eq!(contract.get_highest_bid().bid, U128(300));
+    }
+
+    #[test]
+    fn failed_payment_can_be_retried() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(500)
+            .build());
+        contract.ft_on_transfer("bob.near".parse().unwrap(), U128(200), String::new());
+
+        testing_env!(VMContextBuilder::new().block_timestamp(1500).build());
+        contract.claim();
+
+        // The NFT is delivered but the auctioneer cannot receive the FTs
+        contract.resolve_proceeds(Err(PromiseError::Failed));
+        contract.resolve_nft_delivery(Ok(()));
+        let settlement = contract.get_settlement().unwrap();
+        assert_eq!(settlement.proceeds, LegStatus::Failed);
+        assert_eq!(settlement.nft, LegStatus::Completed);
+        assert_eq!(contract.get_status(), AuctionStatus::Failed);
+
+        // Only the payment is sent again
+        contract.retry_settlement();
+        let settlement = contract.get_settlement().unwrap();
+        assert_eq!(settlement.proceeds, LegStatus::Pending);
+        assert_eq!(settlement.nft, LegStatus::Completed);
+
+        contract.resolve_proceeds(Ok(()));
+        assert_eq!(contract.get_status(), AuctionStatus::Settled);
+    }
+
+    #[test]
+    fn failed_refunds_can_be_withdrawn() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        // Bob is outbid but is not registered with the FT contract
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(500)
+            .build());
+        contract.ft_on_transfer(bob.clone(), U128(200), String::new());
+        contract.ft_on_transfer("carol.near".parse().unwrap(), U128(300), String::new());
+        assert!(!contract.resolve_refund(
+            "ft.near".parse().unwrap(),
+            bob.clone(),
+            U128(200),
+            Err(PromiseError::Failed)
+        ));
+        assert_eq!(contract.get_pending_refund(bob.clone(), None), U128(200));
+
+        // Bob withdraws part of the refund once registered
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(bob.clone())
+            .block_timestamp(600)
+            .build());
+        let _ = contract.withdraw_ft(U128(150), None);
+        assert_eq!(contract.get_pending_refund(bob, None), U128(50));
+    }
+
+    #[test]
+    fn bid_msg_caps_the_bid() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(500)
+            .build());
+        let unused = contract.ft_on_transfer(
+            "bob.near".parse().unwrap(),
+            U128(500),
+            r#"{"max_bid":"200","referral":"carol.near"}"#.to_string(),
+        );
+
+        // Only 200 tokens are bid, the rest is returned
+        assert_eq!(unused, U128(300));
+        assert_eq!(contract.get_highest_bid().bid, U128(200));
+
+        let bids = contract.get_bids(None, None);
+        assert_eq!(bids[0].amount, U128(200));
+        assert_eq!(bids[0].referral, Some("carol.near".parse().unwrap()));
+
+        // A msg that is not a JSON object bids all the tokens transferred
+        let unused =
+            contract.ft_on_transfer("alice.near".parse().unwrap(), U128(300), "0".to_string());
+        assert_eq!(unused, U128(0));
+        assert_eq!(contract.get_highest_bid().bid, U128(300));
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has been cancelled")]
+    fn cancelled_auctions_reject_bids() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .block_timestamp(100)
+            .build());
+        let _ = contract.cancel();
+        assert!(contract.is_cancelled());
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(200)
+            .build());
+        contract.ft_on_transfer("bob.near".parse().unwrap(), U128(200), String::new());
+    }
+
+    #[test]
+    fn failed_cancel_can_be_retried() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "ft.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .block_timestamp(100)
+            .build());
+        let _ = contract.cancel();
+        assert!(contract.is_cancelled());
+
+        // The NFT could not be returned, the auction is back on
+        assert!(!contract.resolve_cancel(Err(PromiseError::Failed)));
+        assert!(!contract.is_cancelled());
+        assert_eq!(contract.get_status(), AuctionStatus::Active);
+
+        let _ = contract.cancel();
+        assert!(contract.resolve_cancel(Ok(())));
+        assert!(contract.is_cancelled());
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has committed bids")]
+    fn committed_bids_block_the_cancellation() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            Some(AuctionMode::Sealed {
+                reveal_end_time: U64::from(2000),
+                second_price: false,
+            }),
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        let msg = serde_json::json!({ "commitment": Base64VecU8::from(vec![0; 32]) }).to_string();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(100)
+            .build());
+        contract.ft_on_transfer("bob.near".parse().unwrap(), U128(200), msg);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .block_timestamp(200)
+            .build());
+        let _ = contract.cancel();
+    }
+
+    #[test]
+    #[should_panic(expected = "Auction has not started yet")]
+    fn bids_wait_for_the_start_time() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(U64::from(500)),
+        );
+        receive_nft(&mut contract, "");
+        assert_eq!(contract.get_status(), AuctionStatus::Pending);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(400)
+            .build());
+        contract.ft_on_transfer("bob.near".parse().unwrap(), U128(200), String::new());
+    }
+
+    #[test]
+    fn proxy_bids_are_raised_up_to_the_maximum() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            ft_contract.clone(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        // Bob is willing to pay up to 500 tokens but only bids the minimum
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract.clone())
+            .block_timestamp(100)
+            .build());
+        let unused = contract.ft_on_transfer(
+            "bob.near".parse().unwrap(),
+            U128(500),
+            r#"{"proxy":true}"#.to_string(),
+        );
+        assert_eq!(unused, U128(0));
+        assert_eq!(contract.get_highest_bid().bid, U128(101));
+
+        // Carol's bid is answered by Bob's proxy and her tokens are returned
+        let unused =
+            contract.ft_on_transfer("carol.near".parse().unwrap(), U128(300), "".to_string());
+        assert_eq!(unused, U128(300));
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder, "bob.near".parse::<AccountId>().unwrap());
+        assert_eq!(highest_bid.bid, U128(301));
+        assert_eq!(contract.get_bid_count(), 3);
+
+        // A higher maximum takes the lead just over Bob's maximum
+        let unused = contract.ft_on_transfer(
+            "carol.near".parse().unwrap(),
+            U128(800),
+            r#"{"proxy":true}"#.to_string(),
+        );
+        assert_eq!(unused, U128(0));
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(
+            highest_bid.bidder,
+            "carol.near".parse::<AccountId>().unwrap()
+        );
+        assert_eq!(highest_bid.bid, U128(501));
+        assert_eq!(contract.escrowed_bid(), U128(800));
+    }
+
+    fn multi_ft_auction() -> Contract {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "ft.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            Some(U128(500)),
+            Some("oracle.near".parse().unwrap()),
+            Some(vec!["usdc.near".parse().unwrap()]),
+            None,
+        );
+        receive_nft(&mut contract, "");
+        contract
+    }
+
+    #[test]
+    fn bids_in_different_fts_are_compared_on_their_value() {
+        let mut contract = multi_ft_auction();
+
+        // One ft.near is worth two usdc.near
+        assert!(contract.resolve_rates(Ok(vec![
+            Some(U128(2_000_000_000_000)),
+            Some(U128(1_000_000_000_000)),
+        ])));
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("usdc.near".parse().unwrap())
+            .block_timestamp(500)
+            .build());
+        contract.ft_on_transfer("bob.near".parse().unwrap(), U128(300), String::new());
+
+        // 160 ft.near are worth more than 300 usdc.near
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("ft.near".parse().unwrap())
+            .block_timestamp(600)
+            .build());
+        contract.ft_on_transfer("carol.near".parse().unwrap(), U128(160), String::new());
+
+        let highest_bid = contract.get_highest_bid();
+        assert_eq!(highest_bid.bidder.as_str(), "carol.near");
+        assert_eq!(highest_bid.bid, U128(160));
+        assert_eq!(highest_bid.ft_contract.as_str(), "ft.near");
+
+        // Bob is refunded in the token he bid with
+        let refund: serde_json::Value =
+            serde_json::from_str(get_logs()[1].strip_prefix("EVENT_JSON:").unwrap()).unwrap();
+        assert_eq!(refund["event"], "bid_refunded");
+        assert_eq!(refund["data"]["ft_contract"], "usdc.near");
+
+        // The buy-now price of 500 ft.near is paid with 1000 usdc.near
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("usdc.near".parse().unwrap())
+            .block_timestamp(700)
+            .build());
+        let unused =
+            contract.ft_on_transfer("bob.near".parse().unwrap(), U128(1100), String::new());
+        assert_eq!(unused, U128(100));
+        assert_eq!(contract.get_highest_bid().bid, U128(1000));
+        assert_eq!(contract.get_status(), AuctionStatus::Settling);
+    }
+
+    #[test]
+    #[should_panic(expected = "The rate of the token is not known yet")]
+    fn bids_wait_for_the_token_rate() {
+        let mut contract = multi_ft_auction();
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("usdc.near".parse().unwrap())
+            .block_timestamp(500)
+            .build());
+        contract.ft_on_transfer("bob.near".parse().unwrap(), U128(300), String::new());
+    }
+
+    #[test]
+    fn claims_without_bids_return_the_nft() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "ft.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        receive_nft(&mut contract, "");
+
+        testing_env!(VMContextBuilder::new().block_timestamp(1500).build());
+        contract.claim();
+
+        assert!(contract.get_auction_info().claimed);
+
+        // There is nothing to pay, only the NFT goes back to the auctioneer
+        let settlement = contract.get_settlement().unwrap();
+        assert_eq!(settlement.proceeds, LegStatus::Completed);
+        assert_eq!(settlement.nft, LegStatus::Pending);
+        assert!(get_logs().is_empty());
+
+        contract.resolve_nft_delivery(Ok(()));
+        assert_eq!(contract.get_status(), AuctionStatus::Settled);
     }
 }
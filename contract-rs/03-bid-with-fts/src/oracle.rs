@@ -0,0 +1,143 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, AccountId, Gas, Promise, PromiseError};
+
+use crate::{price_oracle, Contract, ContractExt};
+
+// Rates are the value of one unit of a token, scaled by 10^12
+const RATE_PRECISION: u128 = 1_000_000_000_000;
+const GET_RATES_GAS: Gas = Gas::from_tgas(10);
+const RATES_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct AcceptedFt {
+    pub ft_contract: AccountId,
+    // Last rate read from the price oracle
+    pub rate: Option<U128>,
+}
+
+#[near]
+impl Contract {
+    // Reads the current rate of every accepted token from the price oracle,
+    // bids in a token are only taken once its rate is known
+    pub fn refresh_rates(&self) -> Promise {
+        let Some(price_oracle) = &self.price_oracle else {
+            env::panic_str("The auction has no price oracle");
+        };
+
+        let ft_contracts = self
+            .accepted_fts
+            .iter()
+            .map(|accepted_ft| accepted_ft.ft_contract.clone())
+            .collect();
+
+        price_oracle::ext(price_oracle.clone())
+            .with_static_gas(GET_RATES_GAS)
+            .get_rates(ft_contracts)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(RATES_CALLBACK_GAS)
+                    .resolve_rates(),
+            )
+    }
+
+    #[private]
+    pub fn resolve_rates(
+        &mut self,
+        #[callback_result] rates: Result<Vec<Option<U128>>, PromiseError>,
+    ) -> bool {
+        let Ok(rates) = rates else {
+            return false;
+        };
+        if rates.len() != self.accepted_fts.len() {
+            return false;
+        }
+
+        for (accepted_ft, rate) in self.accepted_fts.iter_mut().zip(rates) {
+            accepted_ft.rate = rate.filter(|rate| rate.0 > 0);
+        }
+        true
+    }
+
+    pub fn get_accepted_fts(&self) -> Vec<AcceptedFt> {
+        self.accepted_fts.clone()
+    }
+}
+
+impl Contract {
+    pub(crate) fn is_accepted_ft(&self, ft_contract: &AccountId) -> bool {
+        self.accepted_fts
+            .iter()
+            .any(|accepted_ft| &accepted_ft.ft_contract == ft_contract)
+    }
+
+    // Value of an amount of tokens in the unit of the price oracle, bids in
+    // different tokens are compared on it
+    pub(crate) fn value_of(&self, ft_contract: &AccountId, amount: U128) -> u128 {
+        // Without a price oracle only the base token is accepted
+        if self.price_oracle.is_none() {
+            return amount.0;
+        }
+
+        mul_div(amount.0, self.rate_of(ft_contract), RATE_PRECISION, false)
+    }
+
+    // Smallest amount of tokens that is worth at least `value`
+    pub(crate) fn amount_for_value(&self, ft_contract: &AccountId, value: u128) -> U128 {
+        if self.price_oracle.is_none() {
+            return U128(value);
+        }
+
+        U128(mul_div(
+            value,
+            RATE_PRECISION,
+            self.rate_of(ft_contract),
+            true,
+        ))
+    }
+
+    fn rate_of(&self, ft_contract: &AccountId) -> u128 {
+        let rate = self
+            .accepted_fts
+            .iter()
+            .find(|accepted_ft| &accepted_ft.ft_contract == ft_contract)
+            .and_then(|accepted_ft| accepted_ft.rate);
+        require!(
+            rate.is_some(),
+            "The rate of the token is not known yet, call refresh_rates"
+        );
+        rate.unwrap().0
+    }
+}
+
+// The base token comes first, followed by the other accepted tokens
+pub(crate) fn accepted_fts(base_ft: AccountId, other_fts: Vec<AccountId>) -> Vec<AcceptedFt> {
+    let mut ft_contracts = vec![base_ft];
+    for ft_contract in other_fts {
+        if !ft_contracts.contains(&ft_contract) {
+            ft_contracts.push(ft_contract);
+        }
+    }
+
+    ft_contracts
+        .into_iter()
+        .map(|ft_contract| AcceptedFt {
+            ft_contract,
+            rate: None,
+        })
+        .collect()
+}
+
+// a * b / c without overflowing on the intermediate product
+fn mul_div(a: u128, b: u128, c: u128, round_up: bool) -> u128 {
+    let high = (a / c).checked_mul(b);
+    let low = (a % c).checked_mul(b);
+    let result = match (high, low) {
+        (Some(high), Some(low)) => high
+            .checked_add(low / c)
+            .and_then(|result| result.checked_add((round_up && low % c != 0) as u128)),
+        _ => None,
+    };
+    result.unwrap_or_else(|| env::panic_str("The value of the bid is too large"))
+}
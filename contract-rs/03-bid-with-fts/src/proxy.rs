@@ -0,0 +1,113 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::U128;
+use near_sdk::{env, require, AccountId};
+
+use crate::{AuctionEvent, Bid, Contract};
+
+impl Contract {
+    // What the contract holds for the highest bid, the whole maximum of a proxy bid
+    pub(crate) fn escrowed_bid(&self) -> U128 {
+        self.max_bid.unwrap_or(self.highest_bid.bid)
+    }
+
+    // The value the proxy bidder in the lead can still bid up to
+    pub(crate) fn proxy_value(&self) -> Option<u128> {
+        self.max_bid
+            .map(|max_bid| self.value_of(&self.highest_bid.ft_contract, max_bid))
+    }
+
+    // The tokens transferred are the most the bidder is willing to pay, only
+    // what is needed to take the lead over the last bidder is bid
+    pub(crate) fn place_proxy_bid(
+        &mut self,
+        bidder: AccountId,
+        max_bid: U128,
+        ft: AccountId,
+        referral: Option<AccountId>,
+    ) {
+        let last_bid = self.highest_bid.clone();
+        let last_deposit = self.escrowed_bid();
+
+        // The highest bidder can raise their maximum without raising their bid
+        if bidder == last_bid.bidder {
+            require!(
+                ft == last_bid.ft_contract,
+                "The maximum must be raised in the token of the bid"
+            );
+            require!(
+                max_bid.0 > last_deposit.0,
+                "The new maximum must be higher than the current one"
+            );
+            self.max_bid = Some(max_bid);
+            let _ = self.refund_ft(ft, bidder, last_deposit);
+            return;
+        }
+
+        // Take the lead with the smallest bid worth more than what the last
+        // bidder could pay
+        let last_value = self.value_of(&last_bid.ft_contract, last_deposit);
+        let bid = U128(self.amount_for_value(&ft, last_value + 1).0.min(max_bid.0));
+
+        AuctionEvent::BidPlaced {
+            bidder: &bidder,
+            amount: bid,
+            ft_contract: &ft,
+        }
+        .emit();
+        self.record_bid(&bidder, bid, ft.clone(), referral);
+        self.highest_bid = Bid {
+            bidder,
+            bid,
+            ft_contract: ft,
+        };
+        self.max_bid = Some(max_bid);
+
+        // Give other bidders time to respond to a last-minute bid
+        self.extend_auction_end_time();
+
+        if last_bid.bidder != env::current_account_id() {
+            let _ = self.refund_ft(last_bid.ft_contract, last_bid.bidder, last_deposit);
+        }
+    }
+
+    // A bid that does not beat the maximum of the proxy bidder in the lead is
+    // recorded, then the proxy raises its bid over it, the caller returns the
+    // tokens of the bid
+    pub(crate) fn outbid_by_proxy(
+        &mut self,
+        bidder: &AccountId,
+        bid: U128,
+        ft: AccountId,
+        referral: Option<AccountId>,
+    ) {
+        let value = self.value_of(&ft, bid);
+        AuctionEvent::BidPlaced {
+            bidder,
+            amount: bid,
+            ft_contract: &ft,
+        }
+        .emit();
+        self.record_bid(bidder, bid, ft, referral);
+
+        let Bid {
+            bidder: leader,
+            ft_contract: leading_ft,
+            ..
+        } = self.highest_bid.clone();
+        let proxy_bid = U128(
+            self.amount_for_value(&leading_ft, value + 1)
+                .0
+                .min(self.max_bid.unwrap().0),
+        );
+        AuctionEvent::BidPlaced {
+            bidder: &leader,
+            amount: proxy_bid,
+            ft_contract: &leading_ft,
+        }
+        .emit();
+        self.record_bid(&leader, proxy_bid, leading_ft, None);
+        self.highest_bid.bid = proxy_bid;
+
+        self.extend_auction_end_time();
+    }
+}
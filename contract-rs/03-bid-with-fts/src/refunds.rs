@@ -0,0 +1,97 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, AccountId, Gas, NearToken, Promise, PromiseError};
+
+use crate::{ft_contract, AuctionEvent, Contract, ContractExt};
+
+const FT_TRANSFER_GAS: Gas = Gas::from_tgas(30);
+const REFUND_CALLBACK_GAS: Gas = Gas::from_tgas(5);
+
+#[near]
+impl Contract {
+    // Outbid users can pull any refund that could not be pushed to them,
+    // e.g. because they were not registered with the FT contract. Refunds
+    // are kept per token, `ft_contract` defaults to the base token
+    pub fn withdraw_ft(&mut self, amount: U128, ft_contract: Option<AccountId>) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let ft_contract = ft_contract.unwrap_or_else(|| self.ft_contract.clone());
+        let pending = self.get_pending_refund(account_id.clone(), Some(ft_contract.clone()));
+
+        require!(amount.0 > 0, "The amount must be positive");
+        require!(amount <= pending, "The amount exceeds the pending refund");
+
+        let key = (account_id.clone(), ft_contract.clone());
+        let remaining = pending.0 - amount.0;
+        if remaining == 0 {
+            self.pending_refunds.remove(&key);
+        } else {
+            self.pending_refunds.insert(key, U128(remaining));
+        }
+
+        self.refund_ft(ft_contract, account_id, amount)
+    }
+
+    pub fn get_pending_refund(
+        &self,
+        account_id: AccountId,
+        ft_contract: Option<AccountId>,
+    ) -> U128 {
+        let ft_contract = ft_contract.unwrap_or_else(|| self.ft_contract.clone());
+        self.pending_refunds
+            .get(&(account_id, ft_contract))
+            .copied()
+            .unwrap_or(U128(0))
+    }
+
+    #[private]
+    pub fn resolve_refund(
+        &mut self,
+        ft_contract: AccountId,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        if transfer_result.is_ok() {
+            return true;
+        }
+
+        // The transfer failed and the FT contract kept the tokens in this
+        // contract, keep track of them so the user can withdraw them later
+        AuctionEvent::RefundFailed {
+            bidder: &account_id,
+            amount,
+            ft_contract: &ft_contract,
+        }
+        .emit();
+        let pending = self.get_pending_refund(account_id.clone(), Some(ft_contract.clone()));
+        self.pending_refunds
+            .insert((account_id, ft_contract), U128(pending.0 + amount.0));
+        false
+    }
+}
+
+impl Contract {
+    // Pushes a refund, falling back to the pending refunds ledger if it fails
+    pub(crate) fn refund_ft(
+        &self,
+        ft_contract: AccountId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> Promise {
+        AuctionEvent::BidRefunded {
+            bidder: &account_id,
+            amount,
+            ft_contract: &ft_contract,
+        }
+        .emit();
+        ft_contract::ext(ft_contract.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(FT_TRANSFER_GAS)
+            .ft_transfer(account_id.clone(), amount)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(REFUND_CALLBACK_GAS)
+                    .resolve_refund(ft_contract, account_id, amount),
+            )
+    }
+}
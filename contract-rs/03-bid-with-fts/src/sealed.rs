@@ -0,0 +1,216 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde_json;
+use near_sdk::{env, near, require, AccountId};
+
+use crate::{AuctionEvent, AuctionStatus, Bid, Contract, ContractExt};
+
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone)]
+pub enum AuctionMode {
+    // Bids are placed in the open with `ft_transfer_call`
+    Open,
+    // Bids are committed until the auction ends and revealed until `reveal_end_time`,
+    // with `second_price` the winner pays the second highest bid (Vickrey auction)
+    Sealed {
+        reveal_end_time: U64,
+        #[serde(default)]
+        second_price: bool,
+    },
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct Commitment {
+    // sha256 hash of "{bidder}:{bid}:{salt}"
+    pub hash: Base64VecU8,
+    // Tokens transferred with the commitment, must cover the revealed bid
+    pub deposit: U128,
+}
+
+// The `msg` of `ft_transfer_call` when committing to a sealed bid
+#[near(serializers = [json])]
+pub struct CommitMsg {
+    pub commitment: Base64VecU8,
+}
+
+#[near]
+impl Contract {
+    pub fn reveal_bid(&mut self, bid: U128, salt: String) {
+        let AuctionMode::Sealed {
+            reveal_end_time, ..
+        } = self.mode
+        else {
+            env::panic_str("The auction does not take sealed bids");
+        };
+
+        // The reveal phase starts when the auction ends
+        self.assert_status(&[AuctionStatus::Ended]);
+        require!(
+            env::block_timestamp() <= reveal_end_time.into(),
+            "The reveal phase has ended"
+        );
+
+        let bidder = env::predecessor_account_id();
+        let Some(Commitment { hash, deposit }) = self.commitments.get(&bidder).cloned() else {
+            env::panic_str("You have not committed a bid");
+        };
+
+        let preimage = format!("{}:{}:{}", bidder, bid.0, salt);
+        require!(
+            env::sha256(preimage.as_bytes()) == hash.0,
+            "The bid and salt do not match the commitment"
+        );
+        self.commitments.remove(&bidder);
+        self.open_commitments -= 1;
+
+        // A bid that is not covered by the deposit is not valid
+        if bid > deposit {
+            let _ = self.refund_ft(self.ft_contract.clone(), bidder, deposit);
+            return;
+        }
+
+        AuctionEvent::BidRevealed {
+            bidder: &bidder,
+            amount: bid,
+        }
+        .emit();
+        self.record_bid(&bidder, bid, self.ft_contract.clone(), None);
+
+        // A bid that does not beat the current highest bid is refunded,
+        // but it might set the price the winner pays
+        if bid <= self.highest_bid.bid {
+            if self
+                .runner_up_bid
+                .as_ref()
+                .is_none_or(|runner_up| bid > runner_up.bid)
+            {
+                self.runner_up_bid = Some(Bid {
+                    bidder: bidder.clone(),
+                    bid,
+                    ft_contract: self.ft_contract.clone(),
+                });
+            }
+            let _ = self.refund_ft(self.ft_contract.clone(), bidder, deposit);
+            return;
+        }
+
+        let last_bid = std::mem::replace(
+            &mut self.highest_bid,
+            Bid {
+                bidder: bidder.clone(),
+                bid,
+                ft_contract: self.ft_contract.clone(),
+            },
+        );
+
+        // Return the previous highest bid, unless it is the starting price,
+        // and whatever the deposit had on top of the bid
+        if last_bid.bidder != env::current_account_id() {
+            let _ = self.refund_ft(
+                self.ft_contract.clone(),
+                last_bid.bidder.clone(),
+                last_bid.bid,
+            );
+            self.runner_up_bid = Some(last_bid);
+        }
+        if deposit > bid {
+            let _ = self.refund_ft(self.ft_contract.clone(), bidder, U128(deposit.0 - bid.0));
+        }
+    }
+
+    // Commitments that were never revealed can be withdrawn once the reveal
+    // phase is over
+    pub fn withdraw_commitment(&mut self) {
+        let AuctionMode::Sealed {
+            reveal_end_time, ..
+        } = self.mode
+        else {
+            env::panic_str("The auction does not take sealed bids");
+        };
+        require!(
+            env::block_timestamp() > reveal_end_time.into(),
+            "The reveal phase has not ended yet"
+        );
+
+        let bidder = env::predecessor_account_id();
+        let Some(Commitment { deposit, .. }) = self.commitments.remove(&bidder) else {
+            env::panic_str("You have not committed a bid");
+        };
+        self.open_commitments -= 1;
+
+        let _ = self.refund_ft(self.ft_contract.clone(), bidder, deposit);
+    }
+
+    pub fn get_commitment(&self, account_id: AccountId) -> Option<Commitment> {
+        self.commitments.get(&account_id).cloned()
+    }
+
+    pub fn get_auction_mode(&self) -> AuctionMode {
+        self.mode.clone()
+    }
+
+    pub fn get_runner_up_bid(&self) -> Option<Bid> {
+        self.runner_up_bid.clone()
+    }
+
+    // Amount the highest bidder pays when the auction is claimed
+    pub fn get_clearing_price(&self) -> U128 {
+        let AuctionMode::Sealed {
+            second_price: true, ..
+        } = self.mode
+        else {
+            return self.highest_bid.bid;
+        };
+
+        // Without other bids the winner pays the starting price
+        let runner_up = self
+            .runner_up_bid
+            .as_ref()
+            .map_or(self.starting_price, |runner_up| runner_up.bid);
+        runner_up.max(self.starting_price)
+    }
+}
+
+impl Contract {
+    // Called from `ft_on_transfer` while a sealed auction is taking commitments
+    pub(crate) fn commit_bid(&mut self, bidder: AccountId, deposit: U128, msg: String) -> U128 {
+        let CommitMsg { commitment } = serde_json::from_str(&msg)
+            .unwrap_or_else(|_| env::panic_str("The msg must contain the bid commitment"));
+        require!(
+            commitment.0.len() == 32,
+            "The commitment must be a sha256 hash"
+        );
+        require!(
+            !self.commitments.contains_key(&bidder),
+            "You have already committed a bid"
+        );
+
+        AuctionEvent::BidCommitted {
+            bidder: &bidder,
+            deposit,
+        }
+        .emit();
+        self.commitments.insert(
+            bidder,
+            Commitment {
+                hash: commitment,
+                deposit,
+            },
+        );
+        self.open_commitments += 1;
+
+        U128(0)
+    }
+
+    // Sealed auctions can only be claimed once all bids had the chance to be revealed
+    pub(crate) fn settlement_time(&self) -> u64 {
+        match self.mode {
+            AuctionMode::Open => self.auction_end_time.into(),
+            AuctionMode::Sealed {
+                reveal_end_time, ..
+            } => reveal_end_time.into(),
+        }
+    }
+}
@@ -11,6 +11,7 @@ pub struct Bid {
 
 const FT_WASM_FILEPATH: &str = "./tests/fungible_token.wasm";
 const NFT_WASM_FILEPATH: &str = "./tests/non_fungible_token.wasm";
+const ORACLE_MANIFEST_PATH: &str = "./mock-oracle/Cargo.toml";
 
 #[tokio::test]
 
@@ -73,11 +74,11 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .await?
         .assert_success();
 
-    // Mint NFT
+    // Mint NFT to the auctioneer
     const TOKEN_ID: &str = "1";
     let request_payload = json!({
         "token_id": TOKEN_ID,
-        "receiver_id": contract.account_id(),
+        "receiver_id": auctioneer.account_id(),
         "token_metadata": {
             "title": "LEEROYYYMMMJENKINSSS",
             "description": "Alright time's up, let's do this.",
@@ -121,7 +122,7 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
 
     ft_transfer(
         &ft_contract,
-        &ft_contract.account_id(),
+        ft_contract.account_id(),
         alice.account_id(),
         transfer_amount,
         &signer,
@@ -131,7 +132,7 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
 
     ft_transfer(
         &ft_contract,
-        &ft_contract.account_id(),
+        ft_contract.account_id(),
         bob.account_id(),
         transfer_amount,
         &signer,
@@ -164,6 +165,20 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .await?
         .assert_success();
 
+    // The auctioneer transfers the NFT to the contract, which starts the auction
+    nft_contract
+        .call_function(
+            "nft_transfer_call",
+            json!({"receiver_id": contract.account_id(), "token_id": TOKEN_ID, "msg": ""}),
+        )
+        .transaction()
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
     // Alice makes bid less than starting price
     ft_transfer_call(
         &ft_contract,
@@ -335,6 +350,18 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         "token owner is not the highest bidder"
     );
 
+    // Both legs of the settlement went through
+    let settlement: serde_json::Value = contract
+        .call_function("get_settlement", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(
+        settlement,
+        json!({"proceeds": "completed", "nft": "completed"})
+    );
+
     // Auctioneer claims auction back but fails
     contract
         .call_function("claim", ())
@@ -383,6 +410,239 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_bids_in_multiple_fts() -> testresult::TestResult<()> {
+    // Build the auction and the mock price oracle
+    let contract_wasm_path = cargo_near_build::build_with_cli(Default::default())?;
+    let contract_wasm = std::fs::read(contract_wasm_path)?;
+    let oracle_wasm_path = cargo_near_build::build_with_cli(
+        cargo_near_build::BuildOpts::builder()
+            .manifest_path(ORACLE_MANIFEST_PATH)
+            .build(),
+    )?;
+    let oracle_wasm = std::fs::read(oracle_wasm_path)?;
+    let nft_wasm = std::fs::read(NFT_WASM_FILEPATH)?;
+    let ft_wasm = std::fs::read(FT_WASM_FILEPATH)?;
+
+    let sandbox = near_sandbox::Sandbox::start_sandbox().await?;
+    let sandbox_network =
+        near_api::NetworkConfig::from_rpc_url("sandbox", sandbox.rpc_addr.parse()?);
+    let signer = near_api::Signer::from_secret_key(
+        near_sandbox::config::DEFAULT_GENESIS_ACCOUNT_PRIVATE_KEY
+            .parse()
+            .unwrap(),
+    )?;
+
+    let alice = create_subaccount(&sandbox, "alice.sandbox").await?;
+    let bob = create_subaccount(&sandbox, "bob.sandbox").await?;
+    let auctioneer = create_subaccount(&sandbox, "auctioneer.sandbox").await?;
+    let nft_contract = create_subaccount(&sandbox, "nft-contract.sandbox")
+        .await?
+        .as_contract();
+    let ft_contract = create_subaccount(&sandbox, "ft-contract.sandbox")
+        .await?
+        .as_contract();
+    let usd_contract = create_subaccount(&sandbox, "usd-contract.sandbox")
+        .await?
+        .as_contract();
+    let oracle = create_subaccount(&sandbox, "oracle.sandbox")
+        .await?
+        .as_contract();
+    let contract = create_subaccount(&sandbox, "contract.sandbox")
+        .await?
+        .as_contract();
+
+    // Deploy the NFT contract and mint the NFT to the auctioneer
+    near_api::Contract::deploy(nft_contract.account_id().clone())
+        .use_code(nft_wasm)
+        .with_init_call(
+            "new_default_meta",
+            json!({"owner_id": nft_contract.account_id()}),
+        )?
+        .with_signer(signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+    nft_contract
+        .call_function(
+            "nft_mint",
+            json!({"token_id": "1", "receiver_id": auctioneer.account_id(), "token_metadata": {"title": "NFT"}}),
+        )
+        .transaction()
+        .deposit(NearToken::from_millinear(80))
+        .with_signer(nft_contract.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    // Deploy both FT contracts, register the accounts and give tokens to the bidders
+    for token in [&ft_contract, &usd_contract] {
+        near_api::Contract::deploy(token.account_id().clone())
+            .use_code(ft_wasm.clone())
+            .with_init_call(
+                "new_default_meta",
+                json!({"owner_id": token.account_id(), "total_supply": U128(1_000_000)}),
+            )?
+            .with_signer(signer.clone())
+            .send_to(&sandbox_network)
+            .await?
+            .assert_success();
+
+        for account in [
+            alice.clone(),
+            bob.clone(),
+            contract.as_account().clone(),
+            auctioneer.clone(),
+        ] {
+            token
+                .call_function(
+                    "storage_deposit",
+                    json!({ "account_id": account.account_id() }),
+                )
+                .transaction()
+                .deposit(NearToken::from_yoctonear(8000000000000000000000))
+                .with_signer(account.account_id().clone(), signer.clone())
+                .send_to(&sandbox_network)
+                .await?
+                .assert_success();
+        }
+
+        for bidder in [&alice, &bob] {
+            ft_transfer(
+                token,
+                token.account_id(),
+                bidder.account_id(),
+                U128(150_000),
+                &signer,
+                &sandbox_network,
+            )
+            .await?;
+        }
+    }
+
+    // One FT is worth two USD tokens
+    near_api::Contract::deploy(oracle.account_id().clone())
+        .use_code(oracle_wasm)
+        .without_init_call()
+        .with_signer(signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+    for (token, rate) in [
+        (&ft_contract, U128(2_000_000_000_000)),
+        (&usd_contract, U128(1_000_000_000_000)),
+    ] {
+        oracle
+            .call_function(
+                "set_rate",
+                json!({"ft_contract": token.account_id(), "rate": rate}),
+            )
+            .transaction()
+            .with_signer(oracle.account_id().clone(), signer.clone())
+            .send_to(&sandbox_network)
+            .await?
+            .assert_success();
+    }
+
+    // Deploy the auction, priced in FTs but also taking USD tokens
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)?
+        .as_secs();
+    let a_minute_from_now = (now + 60) * 1000000000;
+    near_api::Contract::deploy(contract.account_id().clone())
+        .use_code(contract_wasm)
+        .with_init_call(
+            "init",
+            json!({
+                "end_time": a_minute_from_now.to_string(),
+                "auctioneer": auctioneer.account_id(),
+                "ft_contract": ft_contract.account_id(),
+                "nft_contract": nft_contract.account_id(),
+                "token_id": "1",
+                "starting_price": U128(10_000),
+                "price_oracle": oracle.account_id(),
+                "accepted_fts": [usd_contract.account_id()]
+            }),
+        )?
+        .with_signer(signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    nft_contract
+        .call_function(
+            "nft_transfer_call",
+            json!({"receiver_id": contract.account_id(), "token_id": "1", "msg": ""}),
+        )
+        .transaction()
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    contract
+        .call_function("refresh_rates", ())
+        .transaction()
+        .gas(NearGas::from_tgas(50))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    // Alice bids 30_000 USD tokens, worth more than the starting price of 10_000 FTs
+    ft_transfer_call(
+        &usd_contract,
+        &alice,
+        contract.account_id(),
+        U128(30_000),
+        &signer,
+        &sandbox_network,
+    )
+    .await?;
+
+    // Bob outbids her with 20_000 FTs, and she gets her USD tokens back
+    ft_transfer_call(
+        &ft_contract,
+        &bob,
+        contract.account_id(),
+        U128(20_000),
+        &signer,
+        &sandbox_network,
+    )
+    .await?;
+
+    let highest_bid: Bid = contract
+        .call_function("get_highest_bid", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(&highest_bid.bidder, bob.account_id());
+    assert_eq!(highest_bid.bid, U128(20_000));
+
+    let alice_balance = ft_balance_of(&usd_contract, alice.account_id(), &sandbox_network).await?;
+    assert_eq!(alice_balance, U128(150_000));
+
+    // The auctioneer is paid in the token of the winning bid
+    sandbox.fast_forward(200).await?;
+    contract
+        .call_function("claim", ())
+        .transaction()
+        .gas(NearGas::from_tgas(300))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    let auctioneer_balance =
+        ft_balance_of(&ft_contract, auctioneer.account_id(), &sandbox_network).await?;
+    assert_eq!(auctioneer_balance, U128(20_000));
+
+    Ok(())
+}
+
 async fn create_subaccount(
     sandbox: &near_sandbox::Sandbox,
     name: &str,
@@ -0,0 +1,19 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{near, AccountId};
+
+// NEP-297 events, logged as `EVENT_JSON:{"standard":"auction",...}` so
+// indexers can follow the auction without polling its state
+#[near(event_json(standard = "auction"))]
+pub enum AuctionEvent<'a> {
+    #[event_version("1.0.0")]
+    AuctionCreated {
+        auctioneer: &'a AccountId,
+        end_time: U64,
+    },
+    #[event_version("1.0.0")]
+    AuctionClaimed { winner: &'a AccountId, price: U128 },
+    // Nobody bought the NFT before the end of the auction
+    #[event_version("1.0.0")]
+    AuctionExpired { auctioneer: &'a AccountId },
+}
@@ -0,0 +1,619 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{
+    env, near, require, AccountId, Gas, NearToken, PanicOnDefault, Promise, PromiseError,
+};
+
+pub mod events;
+pub mod ext;
+pub mod price;
+pub use crate::events::*;
+pub use crate::ext::*;
+pub use crate::price::*;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct Sale {
+    pub buyer: AccountId,
+    pub price: U128,
+    // Set once the price was sent to the auctioneer, a payment that failed
+    // is sent again with `claim`
+    pub paid: bool,
+}
+
+pub type TokenId = String;
+
+// The `msg` of `ft_transfer_call` when buying with FTs
+const BUY_NOW_MSG: &str = "buy_now";
+
+const TRANSFER_GAS: Gas = Gas::from_tgas(30);
+const CALLBACK_GAS: Gas = Gas::from_tgas(50);
+const PAYMENT_CALLBACK_GAS: Gas = Gas::from_tgas(5);
+
+#[near(contract_state, serializers = [json, borsh])]
+#[derive(PanicOnDefault)]
+pub struct Contract {
+    starting_price: U128,
+    floor_price: U128,
+    // The price starts going down when the NFT arrives
+    start_time: U64,
+    end_time: U64,
+    decay: PriceDecay,
+    auctioneer: AccountId,
+    // The NFT is paid in NEAR unless a FT contract is set
+    ft_contract: Option<AccountId>,
+    nft_contract: AccountId,
+    token_id: TokenId,
+    // Set once the auctioneer transferred the NFT to the contract
+    nft_received: bool,
+    // Set when someone buys the NFT, and cleared if it could not be delivered
+    sale: Option<Sale>,
+    // Set once the NFT left the contract, to the buyer or back to the auctioneer
+    claimed: bool,
+}
+
+#[near]
+impl Contract {
+    #[init]
+    #[private] // only callable by the contract's account
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        end_time: U64,
+        auctioneer: AccountId,
+        nft_contract: AccountId,
+        token_id: TokenId,
+        starting_price: U128,
+        floor_price: U128,
+        decay: Option<PriceDecay>,
+        ft_contract: Option<AccountId>,
+    ) -> Self {
+        let start_time = env::block_timestamp();
+        require!(
+            end_time.0 > start_time,
+            "The auction must end in the future"
+        );
+        require!(
+            starting_price >= floor_price,
+            "The starting price cannot be lower than the floor price"
+        );
+
+        let decay = decay.unwrap_or(PriceDecay::Linear);
+        if let PriceDecay::Stepwise { step_duration } = decay {
+            require!(step_duration.0 > 0, "step_duration must be positive");
+        }
+
+        AuctionEvent::AuctionCreated {
+            auctioneer: &auctioneer,
+            end_time,
+        }
+        .emit();
+
+        Self {
+            starting_price,
+            floor_price,
+            start_time: U64(start_time),
+            end_time,
+            decay,
+            auctioneer,
+            ft_contract,
+            nft_contract,
+            token_id,
+            nft_received: false,
+            sale: None,
+            claimed: false,
+        }
+    }
+
+    // Called by the NFT contract when the auctioneer transfers the NFT with
+    // `nft_transfer_call`, the NFT can be bought from then on
+    #[allow(unused_variables)]
+    pub fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> bool {
+        require!(
+            env::predecessor_account_id() == self.nft_contract && token_id == self.token_id,
+            "This is not the NFT being auctioned"
+        );
+        require!(
+            previous_owner_id == self.auctioneer,
+            "Only the auctioneer can transfer the NFT"
+        );
+        require!(!self.nft_received, "The NFT has already been received");
+        let now = env::block_timestamp();
+        require!(now < self.end_time.into(), "Auction has ended");
+
+        self.nft_received = true;
+        self.start_time = U64(now);
+
+        // Keep the NFT
+        false
+    }
+
+    // Users buy the NFT at the current price by attaching NEAR
+    #[payable]
+    pub fn buy_now(&mut self) -> Promise {
+        require!(
+            self.ft_contract.is_none(),
+            "The NFT must be bought with ft_transfer_call"
+        );
+        self.assert_on_sale();
+
+        let buyer = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        let price = self.get_current_price();
+        require!(
+            deposit.as_yoctonear() >= price.0,
+            "You must pay at least the current price"
+        );
+
+        self.sale = Some(Sale {
+            buyer: buyer.clone(),
+            price,
+            paid: false,
+        });
+
+        // The buyer is only charged once the NFT was transferred to them
+        self.transfer_nft(buyer).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(CALLBACK_GAS)
+                .resolve_purchase(deposit),
+        )
+    }
+
+    // Users buy the NFT at the current price by transferring FTs
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> Promise {
+        let ft = env::predecessor_account_id();
+        require!(
+            self.ft_contract.as_ref() == Some(&ft),
+            "The token is not supported"
+        );
+        require!(
+            msg == BUY_NOW_MSG,
+            format!("The msg must be \"{BUY_NOW_MSG}\"")
+        );
+        self.assert_on_sale();
+
+        let price = self.get_current_price();
+        require!(amount >= price, "You must pay at least the current price");
+
+        self.sale = Some(Sale {
+            buyer: sender_id.clone(),
+            price,
+            paid: false,
+        });
+
+        // The callback returns the tokens the FT contract gives back to the buyer
+        self.transfer_nft(sender_id).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(CALLBACK_GAS)
+                .resolve_ft_purchase(amount),
+        )
+    }
+
+    // Pays the auctioneer and returns what was paid over the price, or
+    // refunds the buyer if the NFT could not be transferred to them
+    #[private]
+    pub fn resolve_purchase(
+        &mut self,
+        deposit: NearToken,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> Promise {
+        let Sale { buyer, price, .. } = self.sale.clone().unwrap();
+
+        if transfer_result.is_err() {
+            self.sale = None;
+            return Promise::new(buyer).transfer(deposit);
+        }
+
+        self.complete_sale();
+        let change = deposit.saturating_sub(NearToken::from_yoctonear(price.0));
+        if change.is_zero() {
+            return self.pay_auctioneer();
+        }
+        self.pay_auctioneer()
+            .and(Promise::new(buyer).transfer(change))
+    }
+
+    // Same as `resolve_purchase` for FTs, returns the amount of tokens that
+    // the FT contract gives back to the buyer
+    #[private]
+    pub fn resolve_ft_purchase(
+        &mut self,
+        amount: U128,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> U128 {
+        if transfer_result.is_err() {
+            self.sale = None;
+            return amount;
+        }
+
+        self.complete_sale();
+        let price = self.sale.as_ref().unwrap().price;
+        self.pay_auctioneer().detach();
+        U128(amount.0 - price.0)
+    }
+
+    // If nobody bought the NFT it goes back to the auctioneer, if it was sold
+    // and paying the auctioneer failed the payment is sent again
+    pub fn claim(&mut self) -> Promise {
+        if let Some(sale) = &self.sale {
+            require!(self.claimed, "The NFT is being transferred to the buyer");
+            require!(!sale.paid, "Auction has already been claimed");
+            return self.pay_auctioneer();
+        }
+
+        require!(
+            env::block_timestamp() >= self.end_time.into(),
+            "Auction has not ended yet"
+        );
+        require!(self.nft_received, "The contract does not hold the NFT");
+        require!(!self.claimed, "Auction has already been claimed");
+
+        self.claimed = true;
+
+        AuctionEvent::AuctionExpired {
+            auctioneer: &self.auctioneer,
+        }
+        .emit();
+        self.transfer_nft(self.auctioneer.clone()).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(PAYMENT_CALLBACK_GAS)
+                .resolve_return(),
+        )
+    }
+
+    // The NFT can be claimed again if it could not be returned
+    #[private]
+    pub fn resolve_return(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        if transfer_result.is_err() {
+            self.claimed = false;
+        }
+        transfer_result.is_ok()
+    }
+
+    #[private]
+    pub fn resolve_payment(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        if transfer_result.is_err() {
+            self.sale.as_mut().unwrap().paid = false;
+        }
+        transfer_result.is_ok()
+    }
+
+    pub fn get_sale(&self) -> Option<Sale> {
+        self.sale.clone()
+    }
+
+    pub fn get_auction_end_time(&self) -> U64 {
+        self.end_time
+    }
+
+    pub fn get_auction_info(&self) -> &Contract {
+        self
+    }
+}
+
+impl Contract {
+    fn assert_on_sale(&self) {
+        require!(
+            env::block_timestamp() < self.end_time.into(),
+            "Auction has ended"
+        );
+        require!(self.nft_received, "The contract does not hold the NFT yet");
+        require!(self.sale.is_none(), "The NFT has already been sold");
+    }
+
+    fn complete_sale(&mut self) {
+        self.claimed = true;
+        let sale = self.sale.as_ref().unwrap();
+        AuctionEvent::AuctionClaimed {
+            winner: &sale.buyer,
+            price: sale.price,
+        }
+        .emit();
+    }
+
+    // Sends the price of the sale to the auctioneer, in NEAR or in FTs
+    fn pay_auctioneer(&mut self) -> Promise {
+        let sale = self.sale.as_mut().unwrap();
+        sale.paid = true;
+
+        let payment = match &self.ft_contract {
+            Some(ft_contract) => ft_contract::ext(ft_contract.clone())
+                .with_attached_deposit(NearToken::from_yoctonear(1))
+                .with_static_gas(TRANSFER_GAS)
+                .ft_transfer(self.auctioneer.clone(), sale.price),
+            None => Promise::new(self.auctioneer.clone())
+                .transfer(NearToken::from_yoctonear(sale.price.0)),
+        };
+
+        payment.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(PAYMENT_CALLBACK_GAS)
+                .resolve_payment(),
+        )
+    }
+
+    fn transfer_nft(&self, receiver_id: AccountId) -> Promise {
+        nft_contract::ext(self.nft_contract.clone())
+            .with_static_gas(TRANSFER_GAS)
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .nft_transfer(receiver_id, self.token_id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseError};
+
+    use super::*;
+
+    // The auctioneer hands over the NFT, which puts it on sale
+    fn receive_nft(contract: &mut Contract) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("nft.near".parse().unwrap())
+            .build());
+        contract.nft_on_transfer(
+            "auctioneer.near".parse().unwrap(),
+            "auctioneer.near".parse().unwrap(),
+            "1".to_string(),
+            "".to_string(),
+        );
+    }
+
+    #[test]
+    fn init_contract() {
+        let contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            None,
+        );
+
+        let auction_info = contract.get_auction_info();
+        assert_eq!(auction_info.end_time, U64::from(1000));
+        assert_eq!(auction_info.starting_price, U128(1000));
+        assert_eq!(auction_info.floor_price, U128(100));
+        assert!(auction_info.sale.is_none());
+        assert!(!auction_info.claimed);
+
+        assert_eq!(contract.get_current_price(), U128(1000));
+    }
+
+    #[test]
+    fn price_decays_linearly() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            None,
+        );
+        receive_nft(&mut contract);
+
+        assert_eq!(contract.price_at(500), 550);
+        assert_eq!(contract.price_at(999), 101);
+        assert_eq!(contract.price_at(1000), 100);
+        assert_eq!(contract.price_at(5000), 100);
+    }
+
+    #[test]
+    fn price_decays_in_steps() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            Some(PriceDecay::Stepwise {
+                step_duration: U64::from(300),
+            }),
+            None,
+        );
+        receive_nft(&mut contract);
+
+        assert_eq!(contract.price_at(299), 1000);
+        assert_eq!(contract.price_at(300), 730);
+        assert_eq!(contract.price_at(650), 460);
+        assert_eq!(contract.price_at(999), 190);
+        assert_eq!(contract.price_at(1000), 100);
+    }
+
+    #[test]
+    fn price_decays_once_the_nft_arrives() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            None,
+        );
+
+        // The auctioneer only sends the NFT halfway to the end time
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("nft.near".parse().unwrap())
+            .block_timestamp(500)
+            .build());
+        contract.nft_on_transfer(
+            "auctioneer.near".parse().unwrap(),
+            "auctioneer.near".parse().unwrap(),
+            "1".to_string(),
+            "".to_string(),
+        );
+
+        assert_eq!(contract.get_current_price(), U128(1000));
+        assert_eq!(contract.price_at(750), 550);
+        assert_eq!(contract.price_at(1000), 100);
+    }
+
+    #[test]
+    fn buy_now_sells_at_the_current_price() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            None,
+        );
+        receive_nft(&mut contract);
+
+        // Bob pays more than the price, the difference is refunded
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(600))
+            .build());
+        let _ = contract.buy_now();
+
+        let sale = contract.get_sale().unwrap();
+        assert_eq!(sale.buyer, "bob.near".parse::<AccountId>().unwrap());
+        assert_eq!(sale.price, U128(550));
+        assert!(!contract.get_auction_info().claimed);
+
+        // Bob is only charged once the NFT was transferred to him
+        let _ = contract.resolve_purchase(NearToken::from_yoctonear(600), Ok(()));
+        assert!(contract.get_auction_info().claimed);
+        assert!(contract.get_sale().unwrap().paid);
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"auction","version":"1.0.0","event":"auction_claimed","data":{"winner":"bob.near","price":"550"}}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_nft_transfer_puts_the_nft_back_on_sale() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            None,
+        );
+        receive_nft(&mut contract);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(600))
+            .build());
+        let _ = contract.buy_now();
+        let _ =
+            contract.resolve_purchase(NearToken::from_yoctonear(600), Err(PromiseError::Failed));
+
+        // Bob gets his deposit back and someone else can buy the NFT
+        assert!(contract.get_sale().is_none());
+        assert!(!contract.get_auction_info().claimed);
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("alice.near".parse().unwrap())
+            .block_timestamp(600)
+            .attached_deposit(NearToken::from_yoctonear(500))
+            .build());
+        let _ = contract.buy_now();
+        assert_eq!(contract.get_sale().unwrap().price, U128(460));
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract does not hold the NFT yet")]
+    fn buy_now_waits_for_the_nft() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(1000))
+            .build());
+        let _ = contract.buy_now();
+    }
+
+    #[test]
+    #[should_panic(expected = "You must pay at least the current price")]
+    fn buy_now_rejects_underpayment() {
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            None,
+        );
+        receive_nft(&mut contract);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(549))
+            .build());
+        let _ = contract.buy_now();
+    }
+
+    #[test]
+    fn ft_payments_return_the_unused_amount() {
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+        let mut contract = Contract::init(
+            U64::from(1000),
+            "auctioneer.near".parse().unwrap(),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(1000),
+            U128(100),
+            None,
+            Some(ft_contract.clone()),
+        );
+        receive_nft(&mut contract);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(ft_contract)
+            .block_timestamp(250)
+            .build());
+        let _ = contract.ft_on_transfer(
+            "bob.near".parse().unwrap(),
+            U128(1000),
+            BUY_NOW_MSG.to_string(),
+        );
+        assert_eq!(contract.get_sale().unwrap().price, U128(775));
+
+        let unused = contract.resolve_ft_purchase(U128(1000), Ok(()));
+        assert_eq!(unused, U128(225));
+    }
+}
@@ -0,0 +1,47 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near};
+
+use crate::{Contract, ContractExt};
+
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone)]
+pub enum PriceDecay {
+    // The price goes down continuously from the starting price to the floor price
+    Linear,
+    // The price goes down once every `step_duration` nanoseconds
+    Stepwise { step_duration: U64 },
+}
+
+#[near]
+impl Contract {
+    pub fn get_current_price(&self) -> U128 {
+        U128(self.price_at(env::block_timestamp()))
+    }
+}
+
+impl Contract {
+    // Price of the NFT at a given time, it never goes below the floor price
+    pub(crate) fn price_at(&self, timestamp: u64) -> u128 {
+        let start_time: u64 = self.start_time.into();
+        let end_time: u64 = self.end_time.into();
+        if timestamp >= end_time {
+            return self.floor_price.0;
+        }
+
+        let mut elapsed = timestamp.saturating_sub(start_time);
+        if let PriceDecay::Stepwise { step_duration } = self.decay {
+            elapsed -= elapsed % u64::from(step_duration);
+        }
+
+        // Split the division so the multiplication cannot overflow
+        let duration = (end_time - start_time) as u128;
+        let elapsed = elapsed as u128;
+        let price_range = self.starting_price.0 - self.floor_price.0;
+        let decrease =
+            price_range / duration * elapsed + price_range % duration * elapsed / duration;
+
+        self.starting_price.0 - decrease
+    }
+}
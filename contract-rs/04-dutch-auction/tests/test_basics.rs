@@ -0,0 +1,253 @@
+use near_api::{AccountId, NearGas, NearToken};
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+
+#[derive(near_sdk::serde::Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Sale {
+    pub buyer: AccountId,
+    pub price: U128,
+    pub paid: bool,
+}
+
+const NFT_WASM_FILEPATH: &str = "./tests/non_fungible_token.wasm";
+
+#[tokio::test]
+async fn test_contract_is_operational() -> testresult::TestResult<()> {
+    // Build the contract wasm file
+    let contract_wasm_path = cargo_near_build::build_with_cli(Default::default())?;
+    let contract_wasm = std::fs::read(contract_wasm_path)?;
+
+    // Read the NFT wasm file
+    let nft_wasm = std::fs::read(NFT_WASM_FILEPATH)?;
+
+    // Initialize the sandbox
+    let sandbox = near_sandbox::Sandbox::start_sandbox().await?;
+    let sandbox_network =
+        near_api::NetworkConfig::from_rpc_url("sandbox", sandbox.rpc_addr.parse()?);
+
+    // Create accounts
+    let alice = create_subaccount(&sandbox, "alice.sandbox").await?;
+    let bob = create_subaccount(&sandbox, "bob.sandbox").await?;
+    let auctioneer = create_subaccount(&sandbox, "auctioneer.sandbox").await?;
+    let nft_contract = create_subaccount(&sandbox, "nft-contract.sandbox")
+        .await?
+        .as_contract();
+    let contract = create_subaccount(&sandbox, "contract.sandbox")
+        .await?
+        .as_contract();
+
+    // Initialize signer for the contract deployment
+    let signer = near_api::Signer::from_secret_key(
+        near_sandbox::config::DEFAULT_GENESIS_ACCOUNT_PRIVATE_KEY
+            .parse()
+            .unwrap(),
+    )?;
+
+    // Deploy the NFT contract
+    near_api::Contract::deploy(nft_contract.account_id().clone())
+        .use_code(nft_wasm)
+        .with_init_call(
+            "new_default_meta",
+            json!({"owner_id": nft_contract.account_id()}),
+        )?
+        .with_signer(signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    // Mint NFT
+    const TOKEN_ID: &str = "1";
+    let request_payload = json!({
+        "token_id": TOKEN_ID,
+        "receiver_id": auctioneer.account_id(),
+        "token_metadata": {
+            "title": "LEEROYYYMMMJENKINSSS",
+            "description": "Alright time's up, let's do this.",
+            "media": "https://external-content.duckduckgo.com/iu/?u=https%3A%2F%2Ftse3.mm.bing.net%2Fth%3Fid%3DOIP.Fhp4lHufCdTzTeGCAblOdgHaF7%26pid%3DApi&f=1"
+        },
+    });
+
+    nft_contract
+        .call_function("nft_mint", request_payload)
+        .transaction()
+        .deposit(NearToken::from_millinear(80))
+        .with_signer(nft_contract.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    // Deploy the contract with the init call, the price goes from 5 NEAR to 1 NEAR
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)?
+        .as_secs();
+    let a_minute_from_now = (now + 60) * 1000000000;
+    let starting_price = U128(NearToken::from_near(5).as_yoctonear());
+    let floor_price = U128(NearToken::from_near(1).as_yoctonear());
+    near_api::Contract::deploy(contract.account_id().clone())
+        .use_code(contract_wasm)
+        .with_init_call(
+            "init",
+            json!({"end_time": a_minute_from_now.to_string(), "auctioneer": auctioneer.account_id(), "nft_contract": nft_contract.account_id(), "token_id": TOKEN_ID, "starting_price": starting_price, "floor_price": floor_price}),
+        )?
+        .with_signer(signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    // The NFT cannot be bought before the auctioneer transfers it
+    contract
+        .call_function("buy_now", ())
+        .transaction()
+        .deposit(NearToken::from_near(6))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    // The auctioneer transfers the NFT to the contract, which puts it on sale
+    nft_contract
+        .call_function(
+            "nft_transfer_call",
+            json!({"receiver_id": contract.account_id(), "token_id": TOKEN_ID, "msg": ""}),
+        )
+        .transaction()
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    // The auctioneer cannot take the NFT back while the auction is running
+    contract
+        .call_function("claim", ())
+        .transaction()
+        .gas(NearGas::from_tgas(30))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    // Alice tries to buy the NFT below the floor price
+    contract
+        .call_function("buy_now", ())
+        .transaction()
+        .deposit(NearToken::from_millinear(500))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    let bob_balance = bob
+        .tokens()
+        .near_balance()
+        .fetch_from(&sandbox_network)
+        .await?
+        .total;
+
+    // Bob buys the NFT paying more than the starting price
+    contract
+        .call_function("buy_now", ())
+        .transaction()
+        .deposit(NearToken::from_near(6))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(bob.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    let sale: Sale = contract
+        .call_function("get_sale", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(&sale.buyer, bob.account_id());
+    assert!(sale.price <= starting_price);
+    assert!(sale.price >= floor_price);
+    assert!(sale.paid);
+
+    // Bob only paid the price, everything else was refunded
+    let new_bob_balance = bob
+        .tokens()
+        .near_balance()
+        .fetch_from(&sandbox_network)
+        .await?
+        .total;
+    let price = NearToken::from_yoctonear(sale.price.0);
+    assert!(new_bob_balance < bob_balance.saturating_sub(price));
+    assert!(
+        new_bob_balance
+            > bob_balance
+                .saturating_sub(price)
+                .saturating_sub(NearToken::from_millinear(10))
+    );
+
+    // The auctioneer received the price, minus the gas of the NFT transfer and the failed claim
+    let auctioneer_balance = auctioneer
+        .tokens()
+        .near_balance()
+        .fetch_from(&sandbox_network)
+        .await?
+        .total;
+    assert!(auctioneer_balance <= NearToken::from_near(10).saturating_add(price));
+    assert!(auctioneer_balance > NearToken::from_millinear(9990).saturating_add(price));
+
+    // Check the buyer received the NFT
+    let token_info: serde_json::Value = nft_contract
+        .call_function("nft_token", json!({"token_id": TOKEN_ID}))
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    let owner_id: String = token_info["owner_id"].as_str().unwrap().to_string();
+
+    assert_eq!(
+        owner_id,
+        bob.account_id().to_string(),
+        "token owner is not the buyer"
+    );
+
+    // Alice tries to buy the NFT once it has been sold
+    contract
+        .call_function("buy_now", ())
+        .transaction()
+        .deposit(NearToken::from_near(5))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    // Fast forward 200 blocks
+    let blocks_to_advance = 200;
+    sandbox.fast_forward(blocks_to_advance).await?;
+
+    // The auctioneer cannot claim a sold NFT
+    contract
+        .call_function("claim", ())
+        .transaction()
+        .gas(NearGas::from_tgas(30))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    Ok(())
+}
+
+async fn create_subaccount(
+    sandbox: &near_sandbox::Sandbox,
+    name: &str,
+) -> testresult::TestResult<near_api::Account> {
+    let account_id: AccountId = name.parse().unwrap();
+    sandbox
+        .create_account(account_id.clone())
+        .initial_balance(NearToken::from_near(10))
+        .send()
+        .await?;
+    Ok(near_api::Account(account_id))
+}
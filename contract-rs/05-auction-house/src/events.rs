@@ -0,0 +1,41 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{near, AccountId};
+
+use crate::AuctionId;
+
+// NEP-297 events, logged as `EVENT_JSON:{"standard":"auction",...}` so
+// indexers can follow every auction without polling the contract
+#[near(event_json(standard = "auction"))]
+pub enum AuctionEvent<'a> {
+    #[event_version("1.0.0")]
+    AuctionCreated {
+        auction_id: AuctionId,
+        auctioneer: &'a AccountId,
+        end_time: U64,
+    },
+    #[event_version("1.0.0")]
+    BidPlaced {
+        auction_id: AuctionId,
+        bidder: &'a AccountId,
+        amount: U128,
+    },
+    #[event_version("1.0.0")]
+    BidRefunded {
+        auction_id: AuctionId,
+        bidder: &'a AccountId,
+        amount: U128,
+    },
+    #[event_version("1.0.0")]
+    PayoutFailed {
+        receiver: &'a AccountId,
+        amount: U128,
+        ft_contract: Option<&'a AccountId>,
+    },
+    #[event_version("1.0.0")]
+    AuctionClaimed {
+        auction_id: AuctionId,
+        winner: &'a AccountId,
+        price: U128,
+    },
+}
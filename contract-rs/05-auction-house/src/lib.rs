@@ -0,0 +1,656 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::{U128, U64};
+use near_sdk::store::{IterableMap, LookupMap};
+use near_sdk::{
+    env, near, require, AccountId, BorshStorageKey, Gas, NearToken, PanicOnDefault, Promise,
+    PromiseError, PromiseOrValue,
+};
+
+pub mod events;
+pub mod ext;
+pub mod payouts;
+pub use crate::events::*;
+pub use crate::ext::*;
+
+const NEAR_PER_STORAGE: NearToken = NearToken::from_yoctonear(10u128.pow(19)); // 10e19yⓃ
+const DEFAULT_AUCTIONS_LIMIT: u32 = 50;
+const CLAIM_CALLBACK_GAS: Gas = Gas::from_tgas(50);
+
+pub type AuctionId = u64;
+pub type TokenId = String;
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct Bid {
+    pub bidder: AccountId,
+    pub bid: U128,
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct Auction {
+    pub auctioneer: AccountId,
+    pub highest_bid: Bid,
+    pub auction_end_time: U64,
+    pub nft_contract: AccountId,
+    pub token_id: TokenId,
+    // Bids are placed in NEAR unless a FT contract is set
+    pub ft_contract: Option<AccountId>,
+    // Set once the auctioneer transferred the NFT, bids are taken from then on
+    pub nft_received: bool,
+    pub claimed: bool,
+    // Paid by the auctioneer in `create_auction`, returned once the auction is
+    // settled and removed
+    pub storage_deposit: NearToken,
+}
+
+impl Auction {
+    // Without bids the NFT goes back to the auctioneer
+    fn winner(&self) -> &AccountId {
+        if self.highest_bid.bidder == env::current_account_id() {
+            &self.auctioneer
+        } else {
+            &self.highest_bid.bidder
+        }
+    }
+}
+
+// The `msg` of `nft_transfer_call` when the auctioneer hands over the NFT
+#[near(serializers = [json])]
+pub struct AuctionMsg {
+    pub auction_id: AuctionId,
+}
+
+// The `msg` of `ft_transfer_call` when bidding with FTs
+#[near(serializers = [json])]
+pub struct BidMsg {
+    pub auction_id: AuctionId,
+    // Only this amount of the tokens is bid, the rest is returned
+    pub max_bid: Option<U128>,
+}
+
+#[near(serializers = [borsh])]
+#[derive(BorshStorageKey)]
+enum StorageKey {
+    Auctions,
+    PendingPayouts,
+}
+
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct Contract {
+    auctions: IterableMap<AuctionId, Auction>,
+    next_auction_id: AuctionId,
+    pending_payouts: LookupMap<(AccountId, Option<AccountId>), U128>,
+}
+
+#[near]
+impl Contract {
+    #[init]
+    pub fn init() -> Self {
+        Self {
+            auctions: IterableMap::new(StorageKey::Auctions),
+            next_auction_id: 0,
+            pending_payouts: LookupMap::new(StorageKey::PendingPayouts),
+        }
+    }
+
+    // The auction starts once the caller transfers the NFT to this contract
+    // with `nft_transfer_call`, the attached deposit pays for its storage
+    #[payable]
+    pub fn create_auction(
+        &mut self,
+        end_time: U64,
+        nft_contract: AccountId,
+        token_id: TokenId,
+        starting_price: U128,
+        ft_contract: Option<AccountId>,
+    ) -> AuctionId {
+        require!(
+            end_time.0 > env::block_timestamp(),
+            "The auction must end in the future"
+        );
+
+        let auctioneer = env::predecessor_account_id();
+        let auction_id = self.next_auction_id;
+        self.next_auction_id += 1;
+
+        let initial_storage = env::storage_usage();
+        self.auctions.insert(
+            auction_id,
+            Auction {
+                auctioneer: auctioneer.clone(),
+                highest_bid: Bid {
+                    bidder: env::current_account_id(),
+                    bid: starting_price,
+                },
+                auction_end_time: end_time,
+                nft_contract,
+                token_id,
+                ft_contract,
+                nft_received: false,
+                claimed: false,
+                storage_deposit: NearToken::from_yoctonear(0),
+            },
+        );
+        self.auctions.flush();
+
+        let storage_cost =
+            NEAR_PER_STORAGE.saturating_mul((env::storage_usage() - initial_storage) as u128);
+        let attached = env::attached_deposit();
+        require!(
+            attached >= storage_cost,
+            format!("Attach at least {storage_cost} to cover the storage")
+        );
+        self.auctions.get_mut(&auction_id).unwrap().storage_deposit = storage_cost;
+        if attached > storage_cost {
+            let _ =
+                Promise::new(auctioneer.clone()).transfer(attached.saturating_sub(storage_cost));
+        }
+
+        AuctionEvent::AuctionCreated {
+            auction_id,
+            auctioneer: &auctioneer,
+            end_time,
+        }
+        .emit();
+
+        auction_id
+    }
+
+    // Called by the NFT contract when the auctioneer transfers the NFT with
+    // `{"auction_id": <id>}` as `msg`, the auction takes bids from then on
+    #[allow(unused_variables)]
+    pub fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> bool {
+        let AuctionMsg { auction_id } = near_sdk::serde_json::from_str(&msg)
+            .unwrap_or_else(|_| env::panic_str("The msg must contain the auction_id"));
+        let Some(auction) = self.auctions.get_mut(&auction_id) else {
+            env::panic_str("The auction does not exist");
+        };
+        require!(
+            env::predecessor_account_id() == auction.nft_contract && token_id == auction.token_id,
+            "This is not the NFT being auctioned"
+        );
+        require!(
+            previous_owner_id == auction.auctioneer,
+            "Only the auctioneer can transfer the NFT"
+        );
+        require!(!auction.nft_received, "The NFT has already been received");
+        require!(
+            env::block_timestamp() < auction.auction_end_time.into(),
+            "Auction has ended"
+        );
+
+        auction.nft_received = true;
+
+        // Keep the NFT
+        false
+    }
+
+    // Users bid on auctions that take NEAR by attaching it
+    #[payable]
+    pub fn bid(&mut self, auction_id: AuctionId) {
+        let auction = self.get_ongoing_auction(auction_id);
+        require!(
+            auction.ft_contract.is_none(),
+            "Bids on this auction must be placed with ft_transfer_call"
+        );
+
+        let bidder = env::predecessor_account_id();
+        let bid = U128(env::attached_deposit().as_yoctonear());
+        let last_bid = self.place_bid(auction_id, bidder, bid);
+
+        // Transfer tokens back to the last bidder, unless it is the starting price
+        if last_bid.bidder != env::current_account_id() {
+            let _ = self.refund(auction_id, last_bid);
+        }
+    }
+
+    // Users bid on auctions that take FTs by transferring them with
+    // `{"auction_id": <id>}` as `msg`, the amount returned is given back to
+    // them by the FT contract
+    #[allow(unused_must_use)]
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> U128 {
+        let BidMsg {
+            auction_id,
+            max_bid,
+        } = near_sdk::serde_json::from_str(&msg)
+            .unwrap_or_else(|_| env::panic_str("The msg must contain the auction_id"));
+        let bid = max_bid.map_or(amount, |max_bid| U128(amount.0.min(max_bid.0)));
+
+        let auction = self.get_ongoing_auction(auction_id);
+        require!(
+            auction.ft_contract == Some(env::predecessor_account_id()),
+            "The token is not supported"
+        );
+
+        let last_bid = self.place_bid(auction_id, sender_id, bid);
+        if last_bid.bidder != env::current_account_id() {
+            self.refund(auction_id, last_bid);
+        }
+
+        // The tokens over `max_bid` are returned by the FT contract
+        U128(amount.0 - bid.0)
+    }
+
+    pub fn claim(&mut self, auction_id: AuctionId) -> Promise {
+        let Some(auction) = self.auctions.get_mut(&auction_id) else {
+            env::panic_str("The auction does not exist");
+        };
+        require!(
+            env::block_timestamp() > auction.auction_end_time.into(),
+            "Auction has not ended yet"
+        );
+        require!(auction.nft_received, "The auction never received the NFT");
+        require!(!auction.claimed, "Auction has already been claimed");
+
+        auction.claimed = true;
+
+        // Transfer the NFT to the highest bidder, the auctioneer is paid once
+        // it was delivered
+        nft_contract::ext(auction.nft_contract.clone())
+            .with_static_gas(Gas::from_tgas(30))
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .nft_transfer(auction.winner().clone(), auction.token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(CLAIM_CALLBACK_GAS)
+                    .resolve_claim(auction_id),
+            )
+    }
+
+    // If the NFT could not be delivered the auction can be claimed again,
+    // otherwise the auction is settled: it is removed and its storage deposit
+    // goes back to the auctioneer
+    #[private]
+    pub fn resolve_claim(
+        &mut self,
+        auction_id: AuctionId,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> PromiseOrValue<bool> {
+        if transfer_result.is_err() {
+            self.auctions.get_mut(&auction_id).unwrap().claimed = false;
+            return PromiseOrValue::Value(false);
+        }
+
+        let auction = self.auctions.remove(&auction_id).unwrap();
+        AuctionEvent::AuctionClaimed {
+            auction_id,
+            winner: auction.winner(),
+            price: auction.highest_bid.bid,
+        }
+        .emit();
+
+        let _ = Promise::new(auction.auctioneer.clone()).transfer(auction.storage_deposit);
+
+        if auction.highest_bid.bidder == env::current_account_id() {
+            return PromiseOrValue::Value(true);
+        }
+        PromiseOrValue::Promise(self.pay(
+            auction.ft_contract,
+            auction.auctioneer,
+            auction.highest_bid.bid,
+        ))
+    }
+
+    pub fn get_auction(&self, auction_id: AuctionId) -> Option<Auction> {
+        self.auctions.get(&auction_id).cloned()
+    }
+
+    pub fn get_auctions(
+        &self,
+        from_index: Option<u32>,
+        limit: Option<u32>,
+    ) -> Vec<(AuctionId, Auction)> {
+        self.auctions
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(DEFAULT_AUCTIONS_LIMIT) as usize)
+            .map(|(auction_id, auction)| (*auction_id, auction.clone()))
+            .collect()
+    }
+
+    pub fn get_auction_count(&self) -> u32 {
+        self.auctions.len()
+    }
+}
+
+impl Contract {
+    fn get_ongoing_auction(&self, auction_id: AuctionId) -> &Auction {
+        let Some(auction) = self.auctions.get(&auction_id) else {
+            env::panic_str("The auction does not exist");
+        };
+        require!(
+            env::block_timestamp() < auction.auction_end_time.into(),
+            "Auction has ended"
+        );
+        require!(
+            auction.nft_received,
+            "The auction has not received the NFT yet"
+        );
+        auction
+    }
+
+    // Replaces the highest bid of an auction and returns the previous one
+    fn place_bid(&mut self, auction_id: AuctionId, bidder: AccountId, bid: U128) -> Bid {
+        let auction = self.auctions.get_mut(&auction_id).unwrap();
+        require!(bid > auction.highest_bid.bid, "You must place a higher bid");
+
+        AuctionEvent::BidPlaced {
+            auction_id,
+            bidder: &bidder,
+            amount: bid,
+        }
+        .emit();
+
+        std::mem::replace(&mut auction.highest_bid, Bid { bidder, bid })
+    }
+
+    fn refund(&self, auction_id: AuctionId, bid: Bid) -> Promise {
+        AuctionEvent::BidRefunded {
+            auction_id,
+            bidder: &bid.bidder,
+            amount: bid.bid,
+        }
+        .emit();
+
+        let ft_contract = self.auctions.get(&auction_id).unwrap().ft_contract.clone();
+        self.pay(ft_contract, bid.bidder, bid.bid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, PromiseError};
+
+    use super::*;
+
+    // The NFT contract notifies the auction house of a transfer
+    fn receive_nft(contract: &mut Contract, auction_id: AuctionId, previous_owner: &str) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("nft.near".parse().unwrap())
+            .build());
+        contract.nft_on_transfer(
+            previous_owner.parse().unwrap(),
+            previous_owner.parse().unwrap(),
+            "1".to_string(),
+            format!("{{\"auction_id\": {auction_id}}}"),
+        );
+    }
+
+    #[test]
+    fn auctions_are_listed_by_id() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let first = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+        let second = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "2".to_string(),
+            U128(100),
+            Some("ft.near".parse().unwrap()),
+        );
+        assert_eq!(first, 0);
+        assert_eq!(second, 1);
+
+        assert_eq!(contract.get_auction_count(), 2);
+        let auctions = contract.get_auctions(Some(1), None);
+        assert_eq!(auctions.len(), 1);
+        assert_eq!(auctions[0].0, 1);
+        assert_eq!(auctions[0].1.ft_contract, Some("ft.near".parse().unwrap()));
+        assert!(!auctions[0].1.storage_deposit.is_zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach at least")]
+    fn create_auction_requires_storage_deposit() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .build());
+        contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the auctioneer can transfer the NFT")]
+    fn auctions_start_with_the_auctioneers_nft() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("mallory.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let auction_id = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+
+        // Mallory cannot take over an NFT transferred by someone else
+        receive_nft(&mut contract, auction_id, "auctioneer.near");
+    }
+
+    #[test]
+    #[should_panic(expected = "The auction has not received the NFT yet")]
+    fn bids_wait_for_the_nft() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let auction_id = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(200))
+            .build());
+        contract.bid(auction_id);
+    }
+
+    #[test]
+    fn failed_nft_delivery_can_be_claimed_again() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let auction_id = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+        receive_nft(&mut contract, auction_id, "auctioneer.near");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(200))
+            .build());
+        contract.bid(auction_id);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("carol.near".parse().unwrap())
+            .block_timestamp(1500)
+            .build());
+        let _ = contract.claim(auction_id);
+        assert!(contract.get_auction(auction_id).unwrap().claimed);
+
+        let _ = contract.resolve_claim(auction_id, Err(PromiseError::Failed));
+        assert!(!contract.get_auction(auction_id).unwrap().claimed);
+        let _ = contract.claim(auction_id);
+        assert!(contract.get_auction(auction_id).unwrap().claimed);
+    }
+
+    #[test]
+    fn settled_auctions_are_removed() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let auction_id = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+        receive_nft(&mut contract, auction_id, "auctioneer.near");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("carol.near".parse().unwrap())
+            .block_timestamp(1500)
+            .build());
+        let _ = contract.claim(auction_id);
+        let _ = contract.resolve_claim(auction_id, Ok(()));
+
+        assert!(contract.get_auction(auction_id).is_none());
+        assert_eq!(contract.get_auction_count(), 0);
+    }
+
+    #[test]
+    fn failed_payouts_can_be_withdrawn() {
+        let mut contract = Contract::init();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let ft_contract: AccountId = "ft.near".parse().unwrap();
+
+        // Failed payouts are kept per currency
+        contract.resolve_payout(None, bob.clone(), U128(200), Err(PromiseError::Failed));
+        contract.resolve_payout(None, bob.clone(), U128(300), Err(PromiseError::Failed));
+        contract.resolve_payout(
+            Some(ft_contract.clone()),
+            bob.clone(),
+            U128(400),
+            Err(PromiseError::Failed),
+        );
+        contract.resolve_payout(None, bob.clone(), U128(500), Ok(()));
+        assert_eq!(contract.get_pending_payout(bob.clone(), None), U128(500));
+        assert_eq!(
+            contract.get_pending_payout(bob.clone(), Some(ft_contract.clone())),
+            U128(400)
+        );
+
+        // Withdrawing clears the ledger entry
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(bob.clone())
+            .build());
+        let _ = contract.withdraw_payout(None);
+        assert_eq!(contract.get_pending_payout(bob.clone(), None), U128(0));
+        assert_eq!(
+            contract.get_pending_payout(bob, Some(ft_contract)),
+            U128(400)
+        );
+    }
+
+    #[test]
+    fn bids_only_affect_their_auction() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let first = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+        receive_nft(&mut contract, first, "auctioneer.near");
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let second = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            Some("ft.near".parse().unwrap()),
+        );
+        receive_nft(&mut contract, second, "auctioneer.near");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(200))
+            .build());
+        contract.bid(first);
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("ft.near".parse().unwrap())
+            .block_timestamp(500)
+            .build());
+        let unused = contract.ft_on_transfer(
+            "carol.near".parse().unwrap(),
+            U128(400),
+            format!("{{\"auction_id\": {second}, \"max_bid\": \"300\"}}"),
+        );
+        assert_eq!(unused, U128(100));
+
+        let first = contract.get_auction(first).unwrap();
+        assert_eq!(first.highest_bid.bidder.as_str(), "bob.near");
+        assert_eq!(first.highest_bid.bid, U128(200));
+
+        let second = contract.get_auction(second).unwrap();
+        assert_eq!(second.highest_bid.bidder.as_str(), "carol.near");
+        assert_eq!(second.highest_bid.bid, U128(300));
+    }
+
+    #[test]
+    #[should_panic(expected = "You must place a higher bid")]
+    fn bids_must_beat_the_starting_price() {
+        let mut contract = Contract::init();
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("auctioneer.near".parse().unwrap())
+            .attached_deposit(NearToken::from_millinear(100))
+            .build());
+        let auction_id = contract.create_auction(
+            U64::from(1000),
+            "nft.near".parse().unwrap(),
+            "1".to_string(),
+            U128(100),
+            None,
+        );
+        receive_nft(&mut contract, auction_id, "auctioneer.near");
+
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id("bob.near".parse().unwrap())
+            .block_timestamp(500)
+            .attached_deposit(NearToken::from_yoctonear(100))
+            .build());
+        contract.bid(auction_id);
+    }
+}
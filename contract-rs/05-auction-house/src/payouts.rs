@@ -0,0 +1,86 @@
+// Find all our documentation at https://docs.near.org
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, AccountId, Gas, NearToken, Promise, PromiseError};
+
+use crate::{ft_contract, AuctionEvent, Contract, ContractExt};
+
+const FT_TRANSFER_GAS: Gas = Gas::from_tgas(30);
+const PAYOUT_CALLBACK_GAS: Gas = Gas::from_tgas(5);
+
+#[near]
+impl Contract {
+    // Outbid users and auctioneers can pull any payout that could not be
+    // pushed to them, e.g. because they were not registered with the FT
+    // contract. Payouts are kept per currency, `ft_contract` is `None` for NEAR
+    pub fn withdraw_payout(&mut self, ft_contract: Option<AccountId>) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let amount = self
+            .pending_payouts
+            .remove(&(account_id.clone(), ft_contract.clone()))
+            .unwrap_or(U128(0));
+
+        require!(amount.0 > 0, "No pending payout");
+
+        self.pay(ft_contract, account_id, amount)
+    }
+
+    pub fn get_pending_payout(
+        &self,
+        account_id: AccountId,
+        ft_contract: Option<AccountId>,
+    ) -> U128 {
+        self.pending_payouts
+            .get(&(account_id, ft_contract))
+            .copied()
+            .unwrap_or(U128(0))
+    }
+
+    #[private]
+    pub fn resolve_payout(
+        &mut self,
+        ft_contract: Option<AccountId>,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+    ) -> bool {
+        if transfer_result.is_ok() {
+            return true;
+        }
+
+        // The transfer failed and the tokens came back to the contract, keep
+        // track of them so the receiver can withdraw them later
+        AuctionEvent::PayoutFailed {
+            receiver: &account_id,
+            amount,
+            ft_contract: ft_contract.as_ref(),
+        }
+        .emit();
+        let pending = self.get_pending_payout(account_id.clone(), ft_contract.clone());
+        self.pending_payouts
+            .insert((account_id, ft_contract), U128(pending.0 + amount.0));
+        false
+    }
+}
+
+impl Contract {
+    // Sends NEAR or FTs, falling back to the pending payouts ledger if it fails
+    pub(crate) fn pay(
+        &self,
+        ft_contract: Option<AccountId>,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> Promise {
+        let transfer = match &ft_contract {
+            Some(ft_contract) => ft_contract::ext(ft_contract.clone())
+                .with_attached_deposit(NearToken::from_yoctonear(1))
+                .with_static_gas(FT_TRANSFER_GAS)
+                .ft_transfer(receiver_id.clone(), amount),
+            None => Promise::new(receiver_id.clone()).transfer(NearToken::from_yoctonear(amount.0)),
+        };
+        transfer.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(PAYOUT_CALLBACK_GAS)
+                .resolve_payout(ft_contract, receiver_id, amount),
+        )
+    }
+}
@@ -1,107 +1,166 @@
-use near_sdk::json_types::{U128, U64};
-use near_sdk::serde::Serialize;
-use near_sdk::{env, log, near, AccountId, NearToken, Promise, PromiseError};
+use near_sdk::serde_json::{Map, Value};
+use near_sdk::{env, near, AccountId, CryptoHash, NearToken, Promise, PromiseError};
 
+use crate::events::FactoryEvent;
+use crate::manager::TemplateVersion;
+use crate::registry::AuctionRecord;
+use crate::schema::{assert_valid_init_args, init_arg};
 use crate::{Contract, ContractExt, NEAR_PER_STORAGE, NO_DEPOSIT, TGAS};
 
 pub type TokenId = String;
 
-#[derive(Serialize)]
-#[serde(crate = "near_sdk::serde")]
-struct AuctionInitArgs {
-    end_time: U64,
-    auctioneer: AccountId,
-    ft_contract: AccountId,
-    nft_contract: AccountId,
-    token_id: TokenId,
-    starting_price: U128,
-}
+// Kept by the factory to pay for the record of the auction in the registry,
+// what the record does not use is refunded to the creator
+const REGISTRY_STORAGE_DEPOSIT: NearToken = NearToken::from_millinear(10);
+// Caps the size of a record so it always fits in the registry deposit
+const MAX_TOKEN_ID_LEN: usize = 128;
 
 #[near]
 impl Contract {
+    // Deploys an auction of any template, `init_args` are passed to its
+    // `init` method once they are checked against the schema of the template
     #[payable]
     pub fn deploy_new_auction(
         &mut self,
         name: String,
-        end_time: U64,
-        auctioneer: AccountId,
-        ft_contract: AccountId,
-        nft_contract: AccountId,
-        token_id: TokenId,
-        starting_price: U128,
+        template: String,
+        version: Option<u32>,
+        init_args: Map<String, Value>,
     ) -> Promise {
-        // Assert the sub-account is valid
-        let current_account = env::current_account_id().to_string();
-        let subaccount: AccountId = format!("{name}.{current_account}").parse().unwrap();
+        let template_version = self.template_version(&template, version);
+        assert_valid_init_args(&template_version.init_args, &init_args);
+        let token_id: Option<TokenId> = init_arg(&init_args, "token_id");
         assert!(
-            env::is_valid_account_id(subaccount.as_bytes()),
-            "Invalid subaccount"
+            token_id.as_ref().map_or(0, String::len) <= MAX_TOKEN_ID_LEN,
+            "The token_id is too long"
         );
 
-        // Assert enough tokens are attached to create the account and deploy the contract
-        let attached = env::attached_deposit();
-
-        let code = self.code.clone().unwrap();
-        let contract_bytes = code.len() as u128;
-        let contract_storage_cost = NEAR_PER_STORAGE.saturating_mul(contract_bytes);
-        let minimum_needed = contract_storage_cost.saturating_add(NearToken::from_millinear(100));
-        assert!(
-            attached >= minimum_needed,
-            "Attach at least {minimum_needed} yⓃ"
-        );
-
-        let args = &AuctionInitArgs {
-            end_time,
-            auctioneer,
-            ft_contract,
-            nft_contract,
+        let auction = AuctionRecord {
+            account_id: auction_account_id(&name),
+            creator: env::predecessor_account_id(),
+            auctioneer: init_arg(&init_args, "auctioneer").unwrap(),
+            nft_contract: init_arg(&init_args, "nft_contract"),
+            ft_contract: init_arg(&init_args, "ft_contract"),
             token_id,
-            starting_price,
+            end_time: init_arg(&init_args, "end_time").unwrap(),
+            template,
+            code_version: template_version.version,
+            code_hash: template_version.code_hash,
         };
 
-        let init_args = near_sdk::serde_json::to_vec(args).unwrap();
-
-        let promise = Promise::new(subaccount.clone())
-            .create_account()
-            .transfer(attached)
-            .deploy_contract(code)
-            .function_call(
-                "init".to_owned(),
-                init_args,
-                NO_DEPOSIT,
-                TGAS.saturating_mul(5),
-            );
+        let init_args = near_sdk::serde_json::to_vec(&init_args).unwrap();
+        self.deploy_auction(auction, &template_version, init_args)
+    }
 
-        // Add callback
-        promise.then(
-            Self::ext(env::current_account_id()).deploy_new_auction_callback(
-                subaccount,
-                env::predecessor_account_id(),
-                attached,
-            ),
-        )
+    // Deposit needed to deploy an auction of the given template version
+    pub fn get_minimum_deposit(&self, template: String, version: Option<u32>) -> NearToken {
+        self.minimum_deposit(&self.template_version(&template, version))
     }
 
     #[private]
     pub fn deploy_new_auction_callback(
         &mut self,
-        account: AccountId,
-        user: AccountId,
+        auction: AuctionRecord,
         attached: NearToken,
         #[callback_result] create_deploy_result: Result<(), PromiseError>,
     ) -> bool {
         if let Ok(_result) = create_deploy_result {
-            log!("Correctly created and deployed to {}", account);
+            FactoryEvent::AuctionDeployed {
+                auction: &auction.account_id,
+                deployer: &auction.creator,
+            }
+            .emit();
+
+            // Charge the storage the record actually takes
+            let creator = auction.creator.clone();
+            let initial_storage = env::storage_usage();
+            self.record_auction(auction);
+            let storage_cost = env::storage_byte_cost()
+                .saturating_mul((env::storage_usage() - initial_storage) as u128);
+            let _ = Promise::new(creator)
+                .transfer(REGISTRY_STORAGE_DEPOSIT.saturating_sub(storage_cost));
             return true;
         };
 
-        log!(
-            "Error creating {}, returning {}yⓃ to {}",
-            account,
-            attached,
-            user
-        );
-        let _ = Promise::new(user).transfer(attached);
+        FactoryEvent::AuctionDeployFailed {
+            auction: &auction.account_id,
+            deployer: &auction.creator,
+            refunded: attached,
+        }
+        .emit();
+        let _ = Promise::new(auction.creator).transfer(attached);
         false
     }
 }
+
+impl Contract {
+    // Creates the sub-account, deploys the code on it and initializes it
+    // The auction is only added to the registry once it is up and running
+    fn deploy_auction(
+        &self,
+        auction: AuctionRecord,
+        template_version: &TemplateVersion,
+        init_args: Vec<u8>,
+    ) -> Promise {
+        // Assert enough tokens are attached to create the account and deploy the contract
+        let attached = env::attached_deposit();
+        let minimum_needed = self.minimum_deposit(template_version);
+        assert!(
+            attached >= minimum_needed,
+            "Attach at least {minimum_needed} yⓃ"
+        );
+
+        let promise = Promise::new(auction.account_id.clone())
+            .create_account()
+            .transfer(attached.saturating_sub(REGISTRY_STORAGE_DEPOSIT));
+
+        // Code published as a global contract is only referenced by its hash,
+        // otherwise it is copied into the account of the auction
+        let code_hash = CryptoHash::from(template_version.code_hash);
+        let promise = if self.global_code.contains(&code_hash) {
+            promise.use_global_contract(code_hash)
+        } else {
+            promise.deploy_contract(self.template_code(template_version))
+        };
+
+        let promise = promise.function_call(
+            "init".to_owned(),
+            init_args,
+            NO_DEPOSIT,
+            TGAS.saturating_mul(5),
+        );
+
+        // Add callback
+        promise.then(
+            Self::ext(env::current_account_id()).deploy_new_auction_callback(auction, attached),
+        )
+    }
+
+    // Auctions running a global contract do not pay for the storage of their code
+    fn minimum_deposit(&self, template_version: &TemplateVersion) -> NearToken {
+        let code_hash = CryptoHash::from(template_version.code_hash);
+        let contract_storage_cost = if self.global_code.contains(&code_hash) {
+            NearToken::from_yoctonear(0)
+        } else {
+            let contract_bytes = self.code.get(&code_hash).unwrap().len() as u128;
+            NEAR_PER_STORAGE.saturating_mul(contract_bytes)
+        };
+
+        contract_storage_cost
+            .saturating_add(NearToken::from_millinear(100))
+            .saturating_add(REGISTRY_STORAGE_DEPOSIT)
+    }
+}
+
+// Asserts the sub-account for the auction is valid
+fn auction_account_id(name: &str) -> AccountId {
+    let current_account = env::current_account_id().to_string();
+    let subaccount: AccountId = format!("{name}.{current_account}")
+        .parse()
+        .unwrap_or_else(|_| env::panic_str("Invalid subaccount"));
+    assert!(
+        env::is_valid_account_id(subaccount.as_bytes()),
+        "Invalid subaccount"
+    );
+    subaccount
+}
@@ -0,0 +1,43 @@
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::{near, AccountId, NearToken};
+
+// NEP-297 events, logged as `EVENT_JSON:{"standard":"auction",...}` so
+// indexers can discover the auctions created by the factory
+#[near(event_json(standard = "auction"))]
+pub enum FactoryEvent<'a> {
+    #[event_version("1.0.0")]
+    AuctionDeployed {
+        auction: &'a AccountId,
+        deployer: &'a AccountId,
+    },
+    #[event_version("1.0.0")]
+    AuctionDeployFailed {
+        auction: &'a AccountId,
+        deployer: &'a AccountId,
+        refunded: NearToken,
+    },
+    #[event_version("1.0.0")]
+    TemplateVersionAdded {
+        template: &'a str,
+        version: u32,
+        code_hash: &'a Base58CryptoHash,
+    },
+    #[event_version("1.0.0")]
+    CodeUpdateProposed {
+        template: &'a str,
+        code_hash: &'a Base58CryptoHash,
+        apply_after: U64,
+    },
+    #[event_version("1.0.0")]
+    CodeUpdateCancelled {
+        template: &'a str,
+        code_hash: &'a Base58CryptoHash,
+    },
+    #[event_version("1.0.0")]
+    GlobalCodePublished { code_hash: &'a Base58CryptoHash },
+    #[event_version("1.0.0")]
+    OwnerChanged {
+        old_owner: &'a AccountId,
+        new_owner: &'a AccountId,
+    },
+}
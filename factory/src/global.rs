@@ -0,0 +1,53 @@
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{env, near, require, CryptoHash, Promise, PromiseError};
+
+use crate::events::FactoryEvent;
+use crate::{Contract, ContractExt, TGAS};
+
+#[near]
+impl Contract {
+    // Publishes uploaded code as a global contract, auctions running it are
+    // then deployed by referencing its hash instead of copying the code
+    // The factory account pays for the storage of the global contract
+    pub fn publish_global_code(&mut self, code_hash: Base58CryptoHash) -> Promise {
+        self.assert_owner();
+        let hash = CryptoHash::from(code_hash);
+        require!(
+            !self.global_code.contains(&hash),
+            "The code is already a global contract"
+        );
+        let Some(code) = self.code.get(&hash).cloned() else {
+            env::panic_str("The code has not been uploaded");
+        };
+
+        Promise::new(env::current_account_id())
+            .deploy_global_contract(code)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(TGAS.saturating_mul(5))
+                    .publish_global_code_callback(code_hash),
+            )
+    }
+
+    #[private]
+    pub fn publish_global_code_callback(
+        &mut self,
+        code_hash: Base58CryptoHash,
+        #[callback_result] publish_result: Result<(), PromiseError>,
+    ) -> bool {
+        if publish_result.is_err() {
+            return false;
+        }
+
+        FactoryEvent::GlobalCodePublished {
+            code_hash: &code_hash,
+        }
+        .emit();
+        self.global_code.insert(code_hash.into());
+        true
+    }
+
+    pub fn is_global_code(&self, code_hash: Base58CryptoHash) -> bool {
+        self.global_code.contains(&CryptoHash::from(code_hash))
+    }
+}
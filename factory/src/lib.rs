@@ -1,31 +1,172 @@
 // Find all our documentation at https://docs.near.org
-use near_sdk::store::LazyOption;
-use near_sdk::{near, Gas, NearToken};
+use near_sdk::json_types::U64;
+use near_sdk::store::{IterableMap, LazyOption, LookupMap, LookupSet, Vector};
+use near_sdk::{env, near, AccountId, CryptoHash, Gas, NearToken};
+
+use crate::manager::{PendingCodeUpdate, TemplateVersion};
+use crate::registry::AuctionRecord;
+use crate::schema::{ArgKind, InitArg};
 
 mod deploy;
+mod events;
+mod global;
 mod manager;
+mod owner;
+mod registry;
+mod schema;
 
 const NEAR_PER_STORAGE: NearToken = NearToken::from_yoctonear(10u128.pow(19)); // 10e19yⓃ
 const AUCTION_CONTRACT: &[u8] = include_bytes!("./auction-contract/auction.wasm");
+const DUTCH_AUCTION_CONTRACT: &[u8] = include_bytes!("./auction-contract/dutch-auction.wasm");
 const TGAS: Gas = Gas::from_tgas(1);
 const NO_DEPOSIT: NearToken = NearToken::from_near(0); // 0yⓃ
 
+// Templates the factory knows how to deploy, more can be added with
+// `upload_code` and `propose_code_update`
+const FT_AUCTION_TEMPLATE: &str = "ft_auction";
+const DUTCH_AUCTION_TEMPLATE: &str = "dutch_auction";
+
+// Time a code update has to wait before it can be applied, one day by default
+const DEFAULT_CODE_UPDATE_DELAY: U64 = U64(24 * 60 * 60 * 1_000_000_000);
+
 // Define the contract structure
 #[near(contract_state)]
 pub struct Contract {
-    // Since a contract is something big to store, we use LazyOptions
-    // this way it is not deserialized on each method call
-    code: LazyOption<Vec<u8>>,
+    // Since a contract is something big to store, the code is kept in a map
+    // this way it is only deserialized when an auction is deployed
     // Please note that it is much more efficient to **not** store this
     // code in the state, and directly use `AUCTION_CONTRACT`
     // However, this does not enable to update the stored code.
+    code: LookupMap<CryptoHash, Vec<u8>>,
+    // Hashes of the code published as global contracts, auctions running
+    // them only reference the hash instead of storing the code
+    global_code: LookupSet<CryptoHash>,
+    // Account that can upload code and update the templates
+    owner: AccountId,
+    // New versions of the templates only apply after this delay (in nanoseconds)
+    code_update_delay: U64,
+    pending_code_updates: LookupMap<String, PendingCodeUpdate>,
+    // Versions of each template, the last one is the latest
+    templates: IterableMap<String, Vec<TemplateVersion>>,
+    // Registry of the auctions deployed by the factory
+    auctions: Vector<AuctionRecord>,
+    // Each auction of an auctioneer is its own entry, so deploying one more
+    // does not rewrite the ones before it
+    auctions_by_auctioneer: LookupMap<(AccountId, u32), u32>,
+    auctioneer_auction_count: LookupMap<AccountId, u32>,
+}
+
+// State of the factory before it had templates and a registry
+#[near(serializers = [borsh])]
+struct OldContract {
+    code: LazyOption<Vec<u8>>,
 }
 
 // Define the default, which automatically initializes the contract
 impl Default for Contract {
     fn default() -> Self {
+        let mut contract = Self {
+            code: LookupMap::new("code".as_bytes()),
+            global_code: LookupSet::new("global".as_bytes()),
+            owner: env::current_account_id(),
+            code_update_delay: DEFAULT_CODE_UPDATE_DELAY,
+            pending_code_updates: LookupMap::new("pending".as_bytes()),
+            templates: IterableMap::new("templates".as_bytes()),
+            auctions: Vector::new("auctions".as_bytes()),
+            auctions_by_auctioneer: LookupMap::new("by_auctioneer".as_bytes()),
+            auctioneer_auction_count: LookupMap::new("auctioneer_count".as_bytes()),
+        };
+
+        // The bundled auctions are the first version of their templates
+        for (template, code, init_args) in [
+            (FT_AUCTION_TEMPLATE, AUCTION_CONTRACT, ft_auction_init_args()),
+            (
+                DUTCH_AUCTION_TEMPLATE,
+                DUTCH_AUCTION_CONTRACT,
+                dutch_auction_init_args(),
+            ),
+        ] {
+            let code_hash = contract.store_code(code.to_vec());
+            contract.templates.insert(
+                template.to_string(),
+                vec![TemplateVersion {
+                    version: 1,
+                    code_hash: code_hash.into(),
+                    init_args,
+                }],
+            );
+        }
+        contract
+    }
+}
+
+#[near]
+impl Contract {
+    // Initializes the factory with another owner or timelock than the default ones
+    #[init]
+    #[private] // only callable by the contract's account
+    pub fn init(owner: AccountId, code_update_delay: Option<U64>) -> Self {
         Self {
-            code: LazyOption::new("code".as_bytes(), Some(AUCTION_CONTRACT.to_vec())),
+            owner,
+            code_update_delay: code_update_delay.unwrap_or(DEFAULT_CODE_UPDATE_DELAY),
+            ..Self::default()
         }
     }
+
+    // Factories deployed before templates only stored the auction code, it
+    // is dropped and the factory starts over with the bundled auctions
+    #[init(ignore_state)]
+    #[private] // only callable by the contract's account
+    pub fn migrate() -> Self {
+        let mut old_state: OldContract =
+            env::state_read().unwrap_or_else(|| env::panic_str("The factory has no state"));
+        old_state.code.set(None);
+        old_state.code.flush();
+        Self::default()
+    }
+}
+
+// Arguments of the `init` method of the bundled FT auction
+fn ft_auction_init_args() -> Vec<InitArg> {
+    init_args(&[
+        ("end_time", ArgKind::U64, false),
+        ("auctioneer", ArgKind::AccountId, false),
+        ("ft_contract", ArgKind::AccountId, false),
+        ("nft_contract", ArgKind::AccountId, false),
+        ("token_id", ArgKind::String, false),
+        ("starting_price", ArgKind::U128, false),
+        ("extension_window", ArgKind::U64, true),
+        ("extension_duration", ArgKind::U64, true),
+        ("max_extension", ArgKind::U64, true),
+        ("mode", ArgKind::Object, true),
+        ("buy_now_price", ArgKind::U128, true),
+        ("price_oracle", ArgKind::AccountId, true),
+        ("accepted_fts", ArgKind::Array, true),
+        ("start_time", ArgKind::U64, true),
+    ])
+}
+
+// Arguments of the `init` method of the bundled Dutch auction
+fn dutch_auction_init_args() -> Vec<InitArg> {
+    init_args(&[
+        ("end_time", ArgKind::U64, false),
+        ("auctioneer", ArgKind::AccountId, false),
+        ("nft_contract", ArgKind::AccountId, false),
+        ("token_id", ArgKind::String, false),
+        ("starting_price", ArgKind::U128, false),
+        ("floor_price", ArgKind::U128, false),
+        // `"linear"` or `{"stepwise": {"step_duration": "<nanoseconds>"}}`
+        ("decay", ArgKind::Any, true),
+        ("ft_contract", ArgKind::AccountId, true),
+    ])
+}
+
+fn init_args(args: &[(&str, ArgKind, bool)]) -> Vec<InitArg> {
+    args.iter()
+        .map(|(name, kind, optional)| InitArg {
+            name: name.to_string(),
+            kind: *kind,
+            optional: *optional,
+        })
+        .collect()
 }
@@ -1,19 +1,201 @@
-use near_sdk::{env, near};
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::{env, near, require, CryptoHash};
 
+use crate::events::FactoryEvent;
+use crate::schema::{assert_valid_schema, InitArg};
 use crate::{Contract, ContractExt};
 
+// Template names are kept in every auction record, which must fit in the
+// registry deposit
+const MAX_TEMPLATE_NAME_LEN: usize = 64;
+
+// A version of a template, the code it deploys is kept once per hash
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct TemplateVersion {
+    pub version: u32,
+    // sha256 hash of the code
+    pub code_hash: Base58CryptoHash,
+    // Schema of the arguments of the `init` method of the code
+    pub init_args: Vec<InitArg>,
+}
+
+// A new version of a template waiting for the timelock to pass
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct PendingCodeUpdate {
+    pub code_hash: Base58CryptoHash,
+    pub init_args: Vec<InitArg>,
+    // Timestamp from which the update can be applied
+    pub apply_after: U64,
+}
+
+#[near(serializers = [json])]
+pub struct Template {
+    pub name: String,
+    pub versions: Vec<TemplateVersion>,
+}
+
 #[near]
 impl Contract {
-    #[private]
-    pub fn update_auction_contract(&mut self) {
+    pub fn upload_code(&mut self) -> Base58CryptoHash {
+        self.assert_owner();
+
         // This method receives the code to be stored in the contract directly
         // from the contract's input. In this way, it avoids the overhead of
         // deserializing parameters, which would consume a huge amount of GAS
-        self.code.set(env::input());
+        let code = env::input().unwrap_or_else(|| env::panic_str("The code is missing"));
+        self.store_code(code).into()
+    }
+
+    // Proposes uploaded code as the next version of a template, it can only
+    // be applied once `code_update_delay` has passed, so anyone deploying
+    // auctions has time to notice the change
+    pub fn propose_code_update(
+        &mut self,
+        template: String,
+        code_hash: Base58CryptoHash,
+        init_args: Vec<InitArg>,
+    ) -> PendingCodeUpdate {
+        self.assert_owner();
+        require!(!template.is_empty(), "The template needs a name");
+        require!(
+            template.len() <= MAX_TEMPLATE_NAME_LEN,
+            "The template name is too long"
+        );
+        assert_valid_schema(&init_args);
+        require!(
+            self.code.contains_key(&CryptoHash::from(code_hash)),
+            "The code has not been uploaded"
+        );
+        require!(
+            !self.pending_code_updates.contains_key(&template),
+            "The template already has a pending code update"
+        );
+
+        let apply_after = U64(env::block_timestamp() + self.code_update_delay.0);
+        FactoryEvent::CodeUpdateProposed {
+            template: &template,
+            code_hash: &code_hash,
+            apply_after,
+        }
+        .emit();
+
+        let pending = PendingCodeUpdate {
+            code_hash,
+            init_args,
+            apply_after,
+        };
+        self.pending_code_updates.insert(template, pending.clone());
+        pending
+    }
+
+    // Publishes a pending code update as the next version of its template, new
+    // auctions of the template use the latest version unless told otherwise
+    pub fn apply_code_update(&mut self, template: String) -> u32 {
+        self.assert_owner();
+        let Some(pending) = self.pending_code_updates.get(&template) else {
+            env::panic_str("The template has no pending code update");
+        };
+        require!(
+            env::block_timestamp() >= pending.apply_after.0,
+            "The code update is still timelocked"
+        );
+        let PendingCodeUpdate {
+            code_hash,
+            init_args,
+            ..
+        } = self.pending_code_updates.remove(&template).unwrap();
+
+        let versions = self.templates.entry(template.clone()).or_default();
+        let version = versions.len() as u32 + 1;
+        versions.push(TemplateVersion {
+            version,
+            code_hash,
+            init_args,
+        });
+
+        FactoryEvent::TemplateVersionAdded {
+            template: &template,
+            version,
+            code_hash: &code_hash,
+        }
+        .emit();
+        version
+    }
+
+    pub fn cancel_code_update(&mut self, template: String) {
+        self.assert_owner();
+        let Some(PendingCodeUpdate { code_hash, .. }) = self.pending_code_updates.remove(&template)
+        else {
+            env::panic_str("The template has no pending code update");
+        };
+
+        FactoryEvent::CodeUpdateCancelled {
+            template: &template,
+            code_hash: &code_hash,
+        }
+        .emit();
+    }
+
+    pub fn get_pending_code_update(&self, template: String) -> Option<PendingCodeUpdate> {
+        self.pending_code_updates.get(&template).cloned()
+    }
+
+    pub fn get_code_update_delay(&self) -> U64 {
+        self.code_update_delay
+    }
+
+    pub fn get_templates(&self) -> Vec<Template> {
+        self.templates
+            .iter()
+            .map(|(name, versions)| Template {
+                name: name.clone(),
+                versions: versions.clone(),
+            })
+            .collect()
+    }
+
+    pub fn get_template_versions(&self, template: String) -> Vec<TemplateVersion> {
+        self.templates.get(&template).cloned().unwrap_or_default()
     }
 
-    pub fn get_code(&self) -> &Vec<u8> {
+    pub fn get_code(&self, template: String, version: Option<u32>) -> &Vec<u8> {
         // If a contract wants to update themselves, they can ask for the code needed
-        self.code.get().as_ref().unwrap()
+        let TemplateVersion { code_hash, .. } = self.template_version(&template, version);
+        self.code.get(&CryptoHash::from(code_hash)).unwrap()
+    }
+}
+
+impl Contract {
+    // Code is stored under its sha256 hash, so versions that share the code
+    // only pay for its storage once
+    pub(crate) fn store_code(&mut self, code: Vec<u8>) -> CryptoHash {
+        let code_hash = env::sha256_array(&code);
+        self.code.entry(code_hash).or_insert(code);
+        code_hash
+    }
+
+    // The given version of a template, or its latest one
+    pub(crate) fn template_version(&self, template: &str, version: Option<u32>) -> TemplateVersion {
+        let versions = self
+            .templates
+            .get(template)
+            .unwrap_or_else(|| env::panic_str("The template does not exist"));
+
+        let template_version = match version {
+            Some(version) => versions.iter().find(|v| v.version == version),
+            None => versions.last(),
+        };
+        template_version
+            .cloned()
+            .unwrap_or_else(|| env::panic_str("The template version does not exist"))
+    }
+
+    pub(crate) fn template_code(&self, template_version: &TemplateVersion) -> Vec<u8> {
+        self.code
+            .get(&CryptoHash::from(template_version.code_hash))
+            .unwrap()
+            .clone()
     }
 }
@@ -0,0 +1,31 @@
+use near_sdk::{env, near, require, AccountId};
+
+use crate::events::FactoryEvent;
+use crate::{Contract, ContractExt};
+
+#[near]
+impl Contract {
+    pub fn get_owner(&self) -> &AccountId {
+        &self.owner
+    }
+
+    // Hands the management of the templates over to another account
+    pub fn set_owner(&mut self, owner: AccountId) {
+        self.assert_owner();
+        FactoryEvent::OwnerChanged {
+            old_owner: &self.owner,
+            new_owner: &owner,
+        }
+        .emit();
+        self.owner = owner;
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner,
+            "Only the owner can manage the templates"
+        );
+    }
+}
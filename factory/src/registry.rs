@@ -0,0 +1,86 @@
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::{near, AccountId};
+
+use crate::deploy::TokenId;
+use crate::{Contract, ContractExt};
+
+const DEFAULT_AUCTIONS_LIMIT: u32 = 50;
+
+// Every auction the factory deployed successfully
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct AuctionRecord {
+    pub account_id: AccountId,
+    pub creator: AccountId,
+    pub auctioneer: AccountId,
+    // Only set when the template takes them in its init args
+    pub nft_contract: Option<AccountId>,
+    pub ft_contract: Option<AccountId>,
+    pub token_id: Option<TokenId>,
+    pub end_time: U64,
+    // Template and version the auction was deployed from
+    pub template: String,
+    pub code_version: u32,
+    // sha256 hash of the code the auction runs
+    pub code_hash: Base58CryptoHash,
+}
+
+#[near]
+impl Contract {
+    // Auctions in the order they were deployed, starting at `from_index`
+    pub fn get_auctions(&self, from_index: Option<u32>, limit: Option<u32>) -> Vec<AuctionRecord> {
+        self.auctions
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(DEFAULT_AUCTIONS_LIMIT) as usize)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_auctions_by_auctioneer(
+        &self,
+        auctioneer: AccountId,
+        from_index: Option<u32>,
+        limit: Option<u32>,
+    ) -> Vec<AuctionRecord> {
+        let count = self
+            .auctioneer_auction_count
+            .get(&auctioneer)
+            .copied()
+            .unwrap_or(0);
+
+        (from_index.unwrap_or(0)..count)
+            .take(limit.unwrap_or(DEFAULT_AUCTIONS_LIMIT) as usize)
+            .filter_map(|position| {
+                let index = self
+                    .auctions_by_auctioneer
+                    .get(&(auctioneer.clone(), position))?;
+                self.auctions.get(*index).cloned()
+            })
+            .collect()
+    }
+
+    pub fn get_auction_count(&self) -> u32 {
+        self.auctions.len()
+    }
+}
+
+impl Contract {
+    // Writes the record right away, so the caller can measure its storage
+    pub(crate) fn record_auction(&mut self, record: AuctionRecord) {
+        let index = self.auctions.len();
+        let count = self
+            .auctioneer_auction_count
+            .entry(record.auctioneer.clone())
+            .or_default();
+        let position = *count;
+        *count += 1;
+        self.auctions_by_auctioneer
+            .insert((record.auctioneer.clone(), position), index);
+        self.auctions.push(record);
+
+        self.auctions.flush();
+        self.auctions_by_auctioneer.flush();
+        self.auctioneer_auction_count.flush();
+    }
+}
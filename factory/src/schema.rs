@@ -0,0 +1,100 @@
+use near_sdk::serde_json::{Map, Value};
+use near_sdk::{env, near, require, AccountId};
+
+// Kinds of the values an auction can be initialized with, numbers that do
+// not fit in JSON numbers are passed as strings, like `U64` and `U128`
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ArgKind {
+    String,
+    AccountId,
+    U64,
+    U128,
+    Number,
+    Bool,
+    Object,
+    Array,
+    // Anything, validated by the auction itself
+    Any,
+}
+
+// An argument of the `init` method of a template
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct InitArg {
+    pub name: String,
+    pub kind: ArgKind,
+    #[serde(default)]
+    pub optional: bool,
+}
+
+// Every template needs these to be listed in the registry
+const REQUIRED_ARGS: [(&str, ArgKind); 2] = [
+    ("auctioneer", ArgKind::AccountId),
+    ("end_time", ArgKind::U64),
+];
+
+pub(crate) fn assert_valid_schema(schema: &[InitArg]) {
+    for (name, kind) in REQUIRED_ARGS {
+        require!(
+            schema
+                .iter()
+                .any(|arg| arg.name == name && arg.kind == kind && !arg.optional),
+            format!("The init args must include a required `{name}`")
+        );
+    }
+}
+
+// Checks that the init args only have arguments of the schema, of the
+// right kind, and that none of the required ones is missing
+pub(crate) fn assert_valid_init_args(schema: &[InitArg], init_args: &Map<String, Value>) {
+    for name in init_args.keys() {
+        require!(
+            schema.iter().any(|arg| &arg.name == name),
+            format!("Unknown init arg `{name}`")
+        );
+    }
+
+    for arg in schema {
+        match init_args.get(&arg.name) {
+            None | Some(Value::Null) => {
+                require!(arg.optional, format!("Missing init arg `{}`", arg.name))
+            }
+            Some(value) => require!(
+                is_of_kind(value, arg.kind),
+                format!("The init arg `{}` is not a valid {:?}", arg.name, arg.kind)
+            ),
+        }
+    }
+}
+
+fn is_of_kind(value: &Value, kind: ArgKind) -> bool {
+    match kind {
+        ArgKind::String => value.is_string(),
+        ArgKind::AccountId => value
+            .as_str()
+            .is_some_and(|account_id| account_id.parse::<AccountId>().is_ok()),
+        ArgKind::U64 => value.as_str().is_some_and(|n| n.parse::<u64>().is_ok()),
+        ArgKind::U128 => value.as_str().is_some_and(|n| n.parse::<u128>().is_ok()),
+        ArgKind::Number => value.is_number(),
+        ArgKind::Bool => value.is_boolean(),
+        ArgKind::Object => value.is_object(),
+        ArgKind::Array => value.is_array(),
+        ArgKind::Any => true,
+    }
+}
+
+// Reads an argument that was already validated against the schema
+pub(crate) fn init_arg<T: near_sdk::serde::de::DeserializeOwned>(
+    init_args: &Map<String, Value>,
+    name: &str,
+) -> Option<T> {
+    init_args
+        .get(name)
+        .filter(|value| !value.is_null())
+        .map(|value| {
+            near_sdk::serde_json::from_value(value.clone())
+                .unwrap_or_else(|_| env::panic_str(&format!("Invalid init arg `{name}`")))
+        })
+}
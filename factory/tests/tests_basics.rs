@@ -1,8 +1,9 @@
 use near_api::{AccountId, NearGas, NearToken};
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde_json::json;
 
 const FT_WASM_FILEPATH: &str = "./tests/fungible_token.wasm";
+const NFT_WASM_FILEPATH: &str = "./tests/non_fungible_token.wasm";
 
 #[derive(near_sdk::serde::Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -18,7 +19,8 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
     let contract_wasm_path = cargo_near_build::build_with_cli(Default::default())?;
     let contract_wasm = std::fs::read(contract_wasm_path)?;
 
-    // Read the FT wasm file
+    // Read the NFT and FT wasm files
+    let nft_wasm = std::fs::read(NFT_WASM_FILEPATH)?;
     let ft_wasm = std::fs::read(FT_WASM_FILEPATH)?;
 
     // Initialize the sandbox
@@ -35,9 +37,15 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
     let ft_contract = create_subaccount(&sandbox, "ft-contract.sandbox")
         .await?
         .as_contract();
-    let contract = create_subaccount(&sandbox, "contract.sandbox")
-        .await?
-        .as_contract();
+
+    // The factory pays for the storage of the auctions it bundles
+    let contract_id: AccountId = "contract.sandbox".parse().unwrap();
+    sandbox
+        .create_account(contract_id.clone())
+        .initial_balance(NearToken::from_near(100))
+        .send()
+        .await?;
+    let contract = near_api::Account(contract_id).as_contract();
 
     // Initialize signer for the contract deployment
     let signer = near_api::Signer::from_secret_key(
@@ -58,7 +66,29 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .await?
         .assert_success();
 
-    // Skip creating NFT contract as we are are only going to test making a bid to the auction
+    // Deploy the NFT contract and mint the NFT to the auctioneer
+    near_api::Contract::deploy(nft_contract.account_id().clone())
+        .use_code(nft_wasm)
+        .with_init_call(
+            "new_default_meta",
+            json!({"owner_id": nft_contract.account_id()}),
+        )?
+        .with_signer(signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    nft_contract
+        .call_function(
+            "nft_mint",
+            json!({"token_id": "1", "receiver_id": auctioneer.account_id(), "token_metadata": {"title": "NFT"}}),
+        )
+        .transaction()
+        .deposit(NearToken::from_millinear(80))
+        .with_signer(nft_contract.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
 
     // Deploy factory contract
     near_api::Contract::deploy(contract.account_id().clone())
@@ -75,11 +105,12 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .as_secs();
     let a_minute_from_now = (now + 60) * 1000000000;
     let starting_price = U128(10_000);
+    let start_time = U64(now * 1000000000);
 
     contract
-        .call_function("deploy_new_auction", json!({"name": "new-auction", "end_time": a_minute_from_now.to_string(),"auctioneer": auctioneer.account_id(),"ft_contract": ft_contract.account_id(),"nft_contract": nft_contract.account_id(),"token_id":"1", "starting_price":starting_price }),)
+        .call_function("deploy_new_auction", json!({"name": "new-auction", "template": "ft_auction", "init_args": {"end_time": a_minute_from_now.to_string(),"auctioneer": auctioneer.account_id(),"ft_contract": ft_contract.account_id(),"nft_contract": nft_contract.account_id(),"token_id":"1", "starting_price":starting_price, "start_time": start_time }}),)
         .transaction()
-        .deposit(NearToken::from_millinear(1600))
+        .deposit(NearToken::from_near(3))
         .with_signer(alice.account_id().clone(), signer.clone())
         .send_to(&sandbox_network)
         .await?
@@ -88,6 +119,119 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
     let auction_account_id: AccountId = format!("new-auction.{}", contract.account_id())
         .parse()
         .unwrap();
+
+    // The bundled FT auction is the first version of its template
+    let templates: Vec<serde_json::Value> = contract
+        .call_function("get_templates", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0]["name"], "ft_auction");
+    assert_eq!(templates[0]["versions"][0]["version"], 1);
+
+    // Until the code is published as a global contract, auctions pay for storing it
+    let code_hash = templates[0]["versions"][0]["code_hash"].clone();
+    let is_global_code: bool = contract
+        .call_function("is_global_code", json!({"code_hash": code_hash}))
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert!(!is_global_code);
+
+    let minimum_deposit: NearToken = contract
+        .call_function("get_minimum_deposit", json!({"template": "ft_auction"}))
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert!(minimum_deposit > NearToken::from_millinear(2500));
+    assert!(minimum_deposit <= NearToken::from_near(3));
+
+    // Only the owner, the factory account by default, can update the templates
+    let owner: AccountId = contract
+        .call_function("get_owner", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(&owner, contract.account_id());
+
+    contract
+        .call_function("propose_code_update", json!({"template": "ft_auction", "code_hash": code_hash, "init_args": templates[0]["versions"][0]["init_args"]}))
+        .transaction()
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    // Proposed updates wait for the timelock before they can be applied
+    contract
+        .call_function("propose_code_update", json!({"template": "ft_auction", "code_hash": code_hash, "init_args": templates[0]["versions"][0]["init_args"]}))
+        .transaction()
+        .with_signer(contract.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    let pending_update: serde_json::Value = contract
+        .call_function("get_pending_code_update", json!({"template": "ft_auction"}))
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(pending_update["code_hash"], code_hash);
+
+    contract
+        .call_function("apply_code_update", json!({"template": "ft_auction"}))
+        .transaction()
+        .with_signer(contract.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    contract
+        .call_function("cancel_code_update", json!({"template": "ft_auction"}))
+        .transaction()
+        .with_signer(contract.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    let pending_update: Option<serde_json::Value> = contract
+        .call_function("get_pending_code_update", json!({"template": "ft_auction"}))
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert!(pending_update.is_none());
+
+    // The factory keeps a record of the new auction
+    let auction_count: u32 = contract
+        .call_function("get_auction_count", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(auction_count, 1);
+
+    let auctions: Vec<serde_json::Value> = contract
+        .call_function(
+            "get_auctions_by_auctioneer",
+            json!({"auctioneer": auctioneer.account_id()}),
+        )
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(auctions.len(), 1);
+    assert_eq!(auctions[0]["account_id"], auction_account_id.to_string());
+    assert_eq!(auctions[0]["creator"], alice.account_id().to_string());
+    assert_eq!(auctions[0]["token_id"], "1");
+    assert_eq!(auctions[0]["template"], "ft_auction");
+    assert_eq!(auctions[0]["code_version"], 1);
     sandbox
         .import_account(
             sandbox.rpc_addr.to_string(),
@@ -99,6 +243,29 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .await?;
     let auction_account = near_api::Account(auction_account_id.clone()).as_contract();
 
+    // The auction runs the bundled code, which takes the start time
+    let auction_start_time: U64 = auction_account
+        .call_function("get_start_time", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(auction_start_time, start_time);
+
+    // The auctioneer transfers the NFT to the auction, which starts it
+    nft_contract
+        .call_function(
+            "nft_transfer_call",
+            json!({"receiver_id": auction_account_id, "token_id": "1", "msg": ""}),
+        )
+        .transaction()
+        .deposit(NearToken::from_yoctonear(1))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(auctioneer.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
     // Register accounts
     for account_id in [alice.account_id().clone(), auction_account_id.clone()].iter() {
         ft_contract
@@ -159,7 +326,7 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
 
     // Try to launch a new auction with insufficient deposit
     contract
-        .call_function("deploy_new_auction", json!({"name": "new-auction", "end_time": a_minute_from_now.to_string(),"auctioneer": auctioneer.account_id(),"ft_contract": ft_contract.account_id(),"nft_contract": nft_contract.account_id(),"token_id":"1", "starting_price":starting_price }),)
+        .call_function("deploy_new_auction", json!({"name": "new-auction", "template": "ft_auction", "init_args": {"end_time": a_minute_from_now.to_string(),"auctioneer": auctioneer.account_id(),"ft_contract": ft_contract.account_id(),"nft_contract": nft_contract.account_id(),"token_id":"1", "starting_price":starting_price }}),)
         .transaction()
         .deposit(NearToken::from_millinear(1400))
         .with_signer(alice.account_id().clone(), signer.clone())
@@ -167,6 +334,149 @@ async fn test_contract_is_operational() -> testresult::TestResult<()> {
         .await?
         .assert_failure();
 
+    // Init args that do not match the schema of the template are rejected
+    contract
+        .call_function("deploy_new_auction", json!({"name": "other-auction", "template": "ft_auction", "init_args": {"end_time": a_minute_from_now.to_string(),"auctioneer": auctioneer.account_id(),"ft_contract": ft_contract.account_id(),"token_id":"1", "starting_price":starting_price, "floor_price": U128(1_000) }}),)
+        .transaction()
+        .deposit(NearToken::from_near(3))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    // The bundled Dutch auction is deployed like any other template
+    contract
+        .call_function("deploy_new_auction", json!({"name": "new-dutch-auction", "template": "dutch_auction", "init_args": {"end_time": a_minute_from_now.to_string(),"auctioneer": auctioneer.account_id(),"nft_contract": nft_contract.account_id(),"token_id":"1", "starting_price":starting_price, "floor_price": U128(1_000), "decay": "linear" }}),)
+        .transaction()
+        .deposit(NearToken::from_near(3))
+        .gas(NearGas::from_tgas(100))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    let auctions: Vec<serde_json::Value> = contract
+        .call_function(
+            "get_auctions_by_auctioneer",
+            json!({"auctioneer": auctioneer.account_id(), "from_index": 1}),
+        )
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(auctions.len(), 1);
+    assert_eq!(auctions[0]["template"], "dutch_auction");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_global_code_deployments() -> testresult::TestResult<()> {
+    let contract_wasm_path = cargo_near_build::build_with_cli(Default::default())?;
+    let contract_wasm = std::fs::read(contract_wasm_path)?;
+
+    let sandbox = near_sandbox::Sandbox::start_sandbox().await?;
+    let sandbox_network =
+        near_api::NetworkConfig::from_rpc_url("sandbox", sandbox.rpc_addr.parse()?);
+
+    let alice = create_subaccount(&sandbox, "alice.sandbox").await?;
+    let auctioneer = create_subaccount(&sandbox, "auctioneer.sandbox").await?;
+
+    // Publishing a global contract is paid by the factory account
+    let contract_id: AccountId = "contract.sandbox".parse().unwrap();
+    sandbox
+        .create_account(contract_id.clone())
+        .initial_balance(NearToken::from_near(100))
+        .send()
+        .await?;
+    let contract = near_api::Account(contract_id.clone()).as_contract();
+
+    let signer = near_api::Signer::from_secret_key(
+        near_sandbox::config::DEFAULT_GENESIS_ACCOUNT_PRIVATE_KEY
+            .parse()
+            .unwrap(),
+    )?;
+
+    near_api::Contract::deploy(contract_id.clone())
+        .use_code(contract_wasm)
+        .without_init_call()
+        .with_signer(signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    let templates: Vec<serde_json::Value> = contract
+        .call_function("get_templates", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    let code_hash = templates[0]["versions"][0]["code_hash"].clone();
+
+    // Only the owner can publish code as a global contract
+    contract
+        .call_function("publish_global_code", json!({"code_hash": code_hash}))
+        .transaction()
+        .gas(NearGas::from_tgas(300))
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_failure();
+
+    contract
+        .call_function("publish_global_code", json!({"code_hash": code_hash}))
+        .transaction()
+        .gas(NearGas::from_tgas(300))
+        .with_signer(contract_id.clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    let is_global_code: bool = contract
+        .call_function("is_global_code", json!({"code_hash": code_hash}))
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert!(is_global_code);
+
+    // Auctions referencing the global code do not pay for storing it
+    let minimum_deposit: NearToken = contract
+        .call_function("get_minimum_deposit", json!({"template": "ft_auction"}))
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert!(minimum_deposit < NearToken::from_millinear(200));
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)?
+        .as_secs();
+    let a_minute_from_now = (now + 60) * 1000000000;
+
+    contract
+        .call_function("deploy_new_auction", json!({"name": "global-auction", "template": "ft_auction", "init_args": {"end_time": a_minute_from_now.to_string(),"auctioneer": auctioneer.account_id(),"ft_contract": "ft-contract.sandbox","nft_contract": "nft-contract.sandbox","token_id":"1", "starting_price": U128(10_000) }}),)
+        .transaction()
+        .deposit(minimum_deposit)
+        .with_signer(alice.account_id().clone(), signer.clone())
+        .send_to(&sandbox_network)
+        .await?
+        .assert_success();
+
+    // The new auction runs the global code
+    let auction_account_id: AccountId = format!("global-auction.{}", contract_id)
+        .parse()
+        .unwrap();
+    let auction_info: serde_json::Value = near_api::Account(auction_account_id)
+        .as_contract()
+        .call_function("get_auction_info", ())
+        .read_only()
+        .fetch_from(&sandbox_network)
+        .await?
+        .data;
+    assert_eq!(auction_info["auctioneer"], auctioneer.account_id().to_string());
+    assert_eq!(auction_info["auction_end_time"], a_minute_from_now.to_string());
+
     Ok(())
 }
 